@@ -23,6 +23,12 @@ pub enum Message {
         /// If not provided, message will be read from stdin.
         #[structopt(short, long)]
         file: Option<PathBuf>,
+        /// Wait for the node to check the message and print the outcome
+        /// of its submission instead of its id
+        #[structopt(long)]
+        wait: bool,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
     },
 
     /// get the node's logs on the message pool. This will provide information
@@ -35,24 +41,107 @@ pub enum Message {
         debug: DebugFlag,
         #[structopt(flatten)]
         output_format: OutputFormat,
+        /// only get the logs of the messages with the given status,
+        /// one of `pending', `rejected', `in_a_block' or `evicted'
+        #[structopt(long)]
+        status: Option<String>,
+        /// only get the logs of the messages received from the given source,
+        /// one of `network' or `rest'
+        #[structopt(long)]
+        origin: Option<String>,
+    },
+
+    /// get the node's log on the message with the given id
+    Status {
+        #[structopt(flatten)]
+        addr: HostAddr,
+        #[structopt(flatten)]
+        debug: DebugFlag,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+        /// hex-encoded id of the message
+        fragment_id: String,
+    },
+
+    /// get the node's logs on the messages with the given ids. Messages
+    /// unknown to the node are left out of the output
+    Statuses {
+        #[structopt(flatten)]
+        addr: HostAddr,
+        #[structopt(flatten)]
+        debug: DebugFlag,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+        /// hex-encoded ids of the messages
+        fragment_ids: Vec<String>,
     },
 }
 
 impl Message {
     pub fn exec(self) -> Result<(), Error> {
         match self {
-            Message::Post { addr, debug, file } => post_message(file, addr, debug),
+            Message::Post {
+                addr,
+                debug,
+                file,
+                wait,
+                output_format,
+            } => post_message(file, addr, debug, wait, output_format),
             Message::Logs {
                 addr,
                 debug,
                 output_format,
-            } => get_logs(addr, debug, output_format),
+                status,
+                origin,
+            } => get_logs(addr, debug, output_format, status, origin),
+            Message::Status {
+                addr,
+                debug,
+                output_format,
+                fragment_id,
+            } => get_status(addr, debug, output_format, fragment_id),
+            Message::Statuses {
+                addr,
+                debug,
+                output_format,
+                fragment_ids,
+            } => get_statuses(addr, debug, output_format, fragment_ids),
         }
     }
 }
 
-fn get_logs(addr: HostAddr, debug: DebugFlag, output_format: OutputFormat) -> Result<(), Error> {
+fn get_logs(
+    addr: HostAddr,
+    debug: DebugFlag,
+    output_format: OutputFormat,
+    status: Option<String>,
+    origin: Option<String>,
+) -> Result<(), Error> {
     let url = addr.with_segments(&["v0", "fragment", "logs"])?.into_url();
+    let mut builder = reqwest::Client::new().get(url);
+    if let Some(status) = status {
+        builder = builder.query(&[("status", status)]);
+    }
+    if let Some(origin) = origin {
+        builder = builder.query(&[("origin", origin)]);
+    }
+    let response = RestApiSender::new(builder, &debug).send()?;
+    response.ok_response()?;
+    let status = response.body().json_value()?;
+    let formatted = output_format.format_json(status)?;
+    println!("{}", formatted);
+    Ok(())
+}
+
+fn get_status(
+    addr: HostAddr,
+    debug: DebugFlag,
+    output_format: OutputFormat,
+    fragment_id: String,
+) -> Result<(), Error> {
+    let url = addr
+        .with_segments(&["v0", "fragment", &fragment_id])?
+        .into_url();
     let builder = reqwest::Client::new().get(url);
     let response = RestApiSender::new(builder, &debug).send()?;
     response.ok_response()?;
@@ -62,11 +151,37 @@ fn get_logs(addr: HostAddr, debug: DebugFlag, output_format: OutputFormat) -> Re
     Ok(())
 }
 
-fn post_message(file: Option<PathBuf>, addr: HostAddr, debug: DebugFlag) -> Result<(), Error> {
+fn get_statuses(
+    addr: HostAddr,
+    debug: DebugFlag,
+    output_format: OutputFormat,
+    fragment_ids: Vec<String>,
+) -> Result<(), Error> {
+    let url = addr
+        .with_segments(&["v0", "fragment", "statuses"])?
+        .into_url();
+    let builder = reqwest::Client::new().post(url);
+    let response = RestApiSender::new(builder, &debug)
+        .with_json_body(&fragment_ids)?
+        .send()?;
+    response.ok_response()?;
+    let statuses = response.body().json_value()?;
+    let formatted = output_format.format_json(statuses)?;
+    println!("{}", formatted);
+    Ok(())
+}
+
+fn post_message(
+    file: Option<PathBuf>,
+    addr: HostAddr,
+    debug: DebugFlag,
+    wait: bool,
+    output_format: OutputFormat,
+) -> Result<(), Error> {
     let msg_hex = io::read_line(&file)?;
     let msg_bin = hex::decode(&msg_hex)?;
     let url = addr.with_segments(&["v0", "message"])?.into_url();
-    let builder = reqwest::Client::new().post(url);
+    let builder = reqwest::Client::new().post(url).query(&[("wait", wait)]);
     let fragment = Fragment::deserialize(msg_bin.as_slice().into_buf()).map_err(|e| {
         Error::InputFragmentMalformed {
             source: e,
@@ -77,6 +192,12 @@ fn post_message(file: Option<PathBuf>, addr: HostAddr, debug: DebugFlag) -> Resu
         .with_binary_body(msg_bin)
         .send()?;
     response.ok_response()?;
-    println!("{}", fragment.id());
+    if wait {
+        let submission = response.body().json_value()?;
+        let formatted = output_format.format_json(submission)?;
+        println!("{}", formatted);
+    } else {
+        println!("{}", fragment.id());
+    }
     Ok(())
 }
@@ -1,5 +1,7 @@
+mod reputation;
 mod stats;
 
+use self::reputation::Reputation;
 use self::stats::Stats;
 use jcli_app::rest::Error;
 use structopt::StructOpt;
@@ -9,12 +11,15 @@ use structopt::StructOpt;
 pub enum Network {
     /// Network information
     Stats(Stats),
+    /// Reputation of the peers
+    Reputation(Reputation),
 }
 
 impl Network {
     pub fn exec(self) -> Result<(), Error> {
         match self {
             Network::Stats(stats) => stats.exec(),
+            Network::Reputation(reputation) => reputation.exec(),
         }
     }
 }
@@ -1,6 +1,11 @@
-use crate::{crypto::hash::Hash, interfaces::BlockDate, time::SystemTime};
+use crate::{
+    crypto::hash::Hash,
+    interfaces::{BlockDate, NodeId},
+    time::SystemTime,
+};
 use chain_impl_mockchain::key;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 
 /// identify the source of a fragment
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -11,8 +16,14 @@ pub enum FragmentOrigin {
     /// the senders from sending us more fragment (in case
     /// they are invalids or so)
     ///
-    /// TODO: add the network identifier/IP Address
-    Network,
+    /// The node is not known if the fragment was only seen in a
+    /// block received from the network.
+    Network {
+        /// identifier of the network node which sent the fragment
+        node_id: Option<NodeId>,
+        /// remote address of the connection the fragment was received on
+        address: Option<SocketAddr>,
+    },
     /// This marks the fragment is coming from the REST interface
     /// (a client wallet or another service).
     Rest,
@@ -27,6 +38,8 @@ pub enum FragmentStatus {
     Rejected { reason: String },
     /// The fragment has been added in a block
     InABlock { date: BlockDate, block: Hash },
+    /// the fragment has been evicted from a full pool and won't be added in a block
+    Evicted { reason: String },
 }
 
 /// the log associated to a given fragment
@@ -39,6 +52,31 @@ pub struct FragmentLog {
     status: FragmentStatus,
 }
 
+impl FragmentOrigin {
+    /// the fragment was sent by the given network node
+    #[inline]
+    pub fn network(node_id: NodeId, address: Option<SocketAddr>) -> Self {
+        FragmentOrigin::Network {
+            node_id: Some(node_id),
+            address,
+        }
+    }
+
+    #[inline]
+    pub fn is_network(&self) -> bool {
+        if let FragmentOrigin::Network { .. } = &self {
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    pub fn is_rest(&self) -> bool {
+        self == &FragmentOrigin::Rest
+    }
+}
+
 impl FragmentStatus {
     #[inline]
     pub fn is_pending(&self) -> bool {
@@ -62,6 +100,15 @@ impl FragmentStatus {
             false
         }
     }
+
+    #[inline]
+    pub fn is_evicted(&self) -> bool {
+        if let FragmentStatus::Evicted { .. } = &self {
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl FragmentLog {
@@ -92,6 +139,11 @@ impl FragmentLog {
         self.status().is_in_a_block()
     }
 
+    #[inline]
+    pub fn is_evicted(&self) -> bool {
+        self.status().is_evicted()
+    }
+
     /// set the new status
     #[inline]
     pub fn modify(&mut self, new_status: FragmentStatus) {
@@ -0,0 +1,61 @@
+use crate::crypto::hash::Hash;
+use chain_impl_mockchain::key;
+use serde::{Deserialize, Serialize};
+
+/// the outcome of the submission of a fragment to the pool of a node
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FragmentSubmission {
+    fragment_id: Hash,
+    status: SubmissionStatus,
+}
+
+/// whether a submitted fragment has been accepted in the pool
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SubmissionStatus {
+    /// the fragment has been added to the pool and propagated to the network
+    Accepted,
+    /// the fragment has been rejected and won't be added in a block
+    Rejected { reason: String },
+}
+
+impl FragmentSubmission {
+    /// the fragment has been accepted in the pool
+    #[inline]
+    pub fn accepted(fragment_id: key::Hash) -> Self {
+        FragmentSubmission {
+            fragment_id: fragment_id.into(),
+            status: SubmissionStatus::Accepted,
+        }
+    }
+
+    /// the fragment has been rejected for the given reason
+    #[inline]
+    pub fn rejected<R: Into<String>>(fragment_id: key::Hash, reason: R) -> Self {
+        FragmentSubmission {
+            fragment_id: fragment_id.into(),
+            status: SubmissionStatus::Rejected {
+                reason: reason.into(),
+            },
+        }
+    }
+
+    #[inline]
+    pub fn is_accepted(&self) -> bool {
+        self.status == SubmissionStatus::Accepted
+    }
+
+    #[inline]
+    pub fn is_rejected(&self) -> bool {
+        !self.is_accepted()
+    }
+
+    #[inline]
+    pub fn fragment_id(&self) -> &Hash {
+        &self.fragment_id
+    }
+
+    #[inline]
+    pub fn status(&self) -> &SubmissionStatus {
+        &self.status
+    }
+}
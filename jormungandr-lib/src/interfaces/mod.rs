@@ -4,8 +4,10 @@ mod block0_configuration;
 mod blockdate;
 mod certificate;
 mod fragment_log;
+mod fragment_submission;
 mod leadership_log;
 mod linear_fee;
+mod node_id;
 mod old_address;
 mod settings;
 mod transaction_output;
@@ -21,8 +23,10 @@ pub use self::certificate::{
     Certificate, CertificateFromBech32Error, CertificateFromStrError, CertificateToBech32Error,
 };
 pub use self::fragment_log::{FragmentLog, FragmentOrigin, FragmentStatus};
+pub use self::fragment_submission::{FragmentSubmission, SubmissionStatus};
 pub use self::leadership_log::{EnclaveLeaderId, LeadershipLog, LeadershipLogId};
 pub use self::linear_fee::LinearFeeDef;
+pub use self::node_id::{NodeId, NodeIdFromStrError};
 pub use self::old_address::OldAddress;
 pub use self::settings::*;
 pub use self::transaction_output::TransactionOutput;
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+const NODE_ID_SIZE: usize = 32;
+
+/// identifier of a node of the peer to peer network
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId([u8; NODE_ID_SIZE]);
+
+custom_error! {pub NodeIdFromStrError
+    InvalidSize { size: usize } = "invalid node id size, expected 64 hexadecimal characters, got {size}",
+    InvalidHex = "invalid hexadecimal character in the node id",
+}
+
+/* ---------------- Display ------------------------------------------------ */
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NodeId").field(&self.to_string()).finish()
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = NodeIdFromStrError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != NODE_ID_SIZE * 2 {
+            return Err(NodeIdFromStrError::InvalidSize { size: s.len() });
+        }
+        let mut bytes = [0; NODE_ID_SIZE];
+        for (byte, i) in bytes.iter_mut().zip((0..s.len()).step_by(2)) {
+            *byte = s
+                .get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or(NodeIdFromStrError::InvalidHex)?;
+        }
+        Ok(NodeId(bytes))
+    }
+}
+
+/* ---------------- AsRef -------------------------------------------------- */
+
+impl AsRef<[u8]> for NodeId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/* ---------------- Conversion --------------------------------------------- */
+
+impl From<[u8; NODE_ID_SIZE]> for NodeId {
+    fn from(bytes: [u8; NODE_ID_SIZE]) -> Self {
+        NodeId(bytes)
+    }
+}
+
+impl From<NodeId> for [u8; NODE_ID_SIZE] {
+    fn from(node_id: NodeId) -> Self {
+        node_id.0
+    }
+}
+
+/* ------------------- Serde ----------------------------------------------- */
+
+impl Serialize for NodeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            self.to_string().serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            NodeId::from_str(&s).map_err(<D::Error as serde::de::Error>::custom)
+        } else {
+            let bytes = <[u8; NODE_ID_SIZE]>::deserialize(deserializer)?;
+            Ok(NodeId(bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+
+    impl Arbitrary for NodeId {
+        fn arbitrary<G>(g: &mut G) -> Self
+        where
+            G: Gen,
+        {
+            let mut bytes = [0; NODE_ID_SIZE];
+            g.fill_bytes(&mut bytes);
+            NodeId(bytes)
+        }
+    }
+
+    #[test]
+    fn node_id_display() {
+        const EXPECTED_NODE_ID_STR: &'static str =
+            "2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a";
+
+        let node_id = NodeId([0x2a; NODE_ID_SIZE]);
+
+        assert_eq!(node_id.to_string(), EXPECTED_NODE_ID_STR);
+    }
+
+    #[test]
+    fn node_id_from_str_rejects_invalid_input() {
+        assert!("2a2a".parse::<NodeId>().is_err());
+        assert!("zz".repeat(NODE_ID_SIZE).parse::<NodeId>().is_err());
+    }
+
+    quickcheck! {
+        fn node_id_display_and_from_str(node_id: NodeId) -> bool {
+            let decoded: NodeId = node_id.to_string().parse().unwrap();
+            decoded == node_id
+        }
+
+        fn node_id_serde_human_readable(node_id: NodeId) -> bool {
+            let encoded = serde_yaml::to_string(&node_id).unwrap();
+            let decoded: NodeId = serde_yaml::from_str(&encoded).unwrap();
+            decoded == node_id
+        }
+
+        fn node_id_serde_binary(node_id: NodeId) -> bool {
+            let encoded = bincode::serialize(&node_id).unwrap();
+            let decoded: NodeId = bincode::deserialize(&encoded).unwrap();
+            decoded == node_id
+        }
+    }
+}
@@ -255,6 +255,13 @@ impl NodeController {
                         ));
                         return Ok(status);
                     }
+                    Evicted { reason } => {
+                        self.progress_bar.log_info(format!(
+                            "Fragment '{}' evicted: {}",
+                            check.fragment_id, reason
+                        ));
+                        return Ok(status);
+                    }
                 }
             } else {
                 // bail!(ErrorKind::FragmentNoInMemPoolLogs(
@@ -51,14 +51,18 @@ See Internal documentation for more details: doc/internal_design.md
 
 use crate::{
     blockcfg::{
-        Block, Block0Error, Epoch, Header, HeaderHash, Leadership, Ledger, LedgerParameters,
+        Block, Block0Error, ChainLength, Epoch, Header, HeaderHash, Leadership, Ledger,
+        LedgerParameters,
     },
     blockchain::{Branch, Branches, Checkpoints, Multiverse, Ref, RefCache, Storage},
+    settings::start::config::ServedBlocks,
     start_up::NodeStorage,
+    utils::broadcast::Broadcaster,
 };
 use chain_impl_mockchain::{leadership::Verification, ledger};
 use chain_storage::error::Error as StorageError;
 use chain_time::TimeFrame;
+use futures::sync::mpsc::Receiver;
 use std::{convert::Infallible, sync::Arc, time::Duration};
 use tokio::prelude::*;
 
@@ -123,6 +127,12 @@ pub struct Blockchain {
     ledgers: Multiverse<Arc<Ledger>>,
 
     storage: Storage,
+
+    /// headers of the blocks newly applied and stored
+    new_blocks: Broadcaster<Header>,
+
+    /// how much of the history is served from the storage
+    served_blocks: ServedBlocks,
 }
 
 pub enum PreCheckedHeader {
@@ -161,6 +171,17 @@ pub enum PreCheckedHeader {
     },
 }
 
+/// result of the verification of the blocks of the storage, see
+/// `Blockchain::verify_storage`
+pub struct StorageVerification {
+    /// the block pointed to by the `HEAD` tag, if the tag is set
+    pub head: Option<HeaderHash>,
+    /// the last block successfully loaded from the storage
+    pub last_valid: Arc<Ref>,
+    /// the error preventing to load the next block, if any
+    pub error: Option<Error>,
+}
+
 pub struct PostCheckedHeader {
     header: Header,
     epoch_leadership_schedule: Arc<Leadership>,
@@ -171,12 +192,14 @@ pub struct PostCheckedHeader {
 }
 
 impl Blockchain {
-    pub fn new(storage: NodeStorage, ref_cache_ttl: Duration) -> Self {
+    pub fn new(storage: NodeStorage, ref_cache_ttl: Duration, served_blocks: ServedBlocks) -> Self {
         Blockchain {
             branches: Branches::new(),
             ref_cache: RefCache::new(ref_cache_ttl),
             ledgers: Multiverse::new(),
             storage: Storage::new(storage),
+            new_blocks: Broadcaster::new(),
+            served_blocks,
         }
     }
 
@@ -192,6 +215,55 @@ impl Blockchain {
         &mut self.branches
     }
 
+    /// With `ServedBlocks::Recent`, stops serving the blocks which are older
+    /// than the configured number of epochs before the epoch of `tip`,
+    /// still serving the ones which can be rolled back.
+    ///
+    /// Returns the chain length of the most recent block no longer served,
+    /// if any.
+    pub fn limit_served_blocks(
+        &self,
+        tip: &Ref,
+    ) -> impl Future<Item = Option<ChainLength>, Error = StorageError> {
+        match self.last_unserved_block(tip) {
+            None => future::Either::A(future::ok(None)),
+            Some(chain_length) => future::Either::B(
+                self.storage
+                    .stop_serving_up_to(tip.hash(), tip.chain_length(), chain_length)
+                    .map(move |()| Some(chain_length)),
+            ),
+        }
+    }
+
+    /// the chain length of the most recent block to stop serving, if any
+    fn last_unserved_block(&self, tip: &Ref) -> Option<ChainLength> {
+        let epochs = match self.served_blocks {
+            ServedBlocks::All => return None,
+            ServedBlocks::Recent { epochs } => epochs,
+        };
+        let oldest_served_epoch = tip.block_date().epoch.checked_sub(epochs)?;
+
+        // the last block of the first epoch to stop serving
+        let mut last_unserved = tip.last_ref_previous_epoch()?;
+        while last_unserved.block_date().epoch >= oldest_served_epoch {
+            last_unserved = last_unserved.last_ref_previous_epoch()?;
+        }
+
+        let stable_chain_length =
+            u32::from(tip.chain_length()).checked_sub(tip.epoch_stability_depth())?;
+        if u32::from(last_unserved.chain_length()) <= stable_chain_length {
+            Some(last_unserved.chain_length())
+        } else {
+            Some(ChainLength::from(stable_chain_length))
+        }
+    }
+
+    /// Returns the stream of the headers of the blocks applied and stored
+    /// from now on, whether or not they end up on the main branch.
+    pub fn subscribe_to_new_blocks(&self) -> Receiver<Header> {
+        self.new_blocks.subscribe()
+    }
+
     /// create and store a reference of this leader to the new
     fn create_and_store_reference(
         &mut self,
@@ -440,7 +512,12 @@ impl Blockchain {
         }
     }
 
-    fn apply_block(
+    /// Apply the block on the blockchain from a post checked header,
+    /// without adding it to the storage.
+    ///
+    /// The block has to be stored with `store_blocks` before the returned
+    /// `Ref` is selected as a tip.
+    pub fn apply_block(
         &mut self,
         post_checked_header: PostCheckedHeader,
         block: &Block,
@@ -487,15 +564,41 @@ impl Blockchain {
         block: Block,
     ) -> impl Future<Item = Arc<Ref>, Error = Error> {
         let mut storage = self.storage.clone();
+        let new_blocks = self.new_blocks.clone();
         self.apply_block(post_checked_header, &block)
             .and_then(move |block_ref| {
                 storage
                     .put_block(block)
+                    .map(|()| true)
                     .or_else(|err| match err {
-                        StorageError::BlockAlreadyPresent => Ok(()),
+                        StorageError::BlockAlreadyPresent => Ok(false),
                         err => Err(err.into()),
                     })
-                    .and_then(move |()| Ok(block_ref))
+                    .map(move |stored| {
+                        if stored {
+                            new_blocks.send(block_ref.header().clone());
+                        }
+                        block_ref
+                    })
+            })
+    }
+
+    /// Add the blocks, already applied with `apply_block`, to the storage
+    /// in one go, move the tag to the last one and notify the subscribers
+    /// of the newly stored blocks.
+    pub fn store_blocks(
+        &mut self,
+        blocks: Vec<Block>,
+        tag: &str,
+    ) -> impl Future<Item = (), Error = Error> {
+        let new_blocks = self.new_blocks.clone();
+        self.storage
+            .put_blocks_and_tag(blocks, tag.to_owned())
+            .map_err(|err| err.into())
+            .map(move |headers| {
+                for header in headers {
+                    new_blocks.send(header);
+                }
             })
     }
 
@@ -696,46 +799,159 @@ impl Blockchain {
                     .and_then(move |block_stream| {
                         block_stream
                             .map_err(|e| {
-                                Error::with_chain(e, "Error while iterating between block0 and HEAD")
+                                Error::with_chain(
+                                    e,
+                                    "Error while iterating between block0 and HEAD",
+                                )
                             })
-                            .fold((branch, self4), move |(branch, mut self4), block: Block| {
-                                let header = block.header.clone();
-
-                                let mut self5 = self4.clone();
-                                let mut self6 = self4.clone();
+                            .fold((branch, self4), move |(branch, self4), block: Block| {
                                 let returned = self4.clone();
 
-                                self4
-                                    .pre_check_header(header, true)
-                                    .and_then(move |pre_checked_header: PreCheckedHeader| {
-                                        match pre_checked_header {
-                                            PreCheckedHeader::HeaderWithCache {
-                                                header,
-                                                parent_ref,
-                                            } => future::Either::A(self5.post_check_header(header, parent_ref)),
-                                            PreCheckedHeader::AlreadyPresent { header, cached_reference: _cached_reference } => {
-                                                unreachable!("block already present, this should not happen. {:#?}", header)
-                                            },
-                                            PreCheckedHeader::MissingParent { header } =>
-                                                future::Either::B(future::err(ErrorKind::MissingParentBlockFromStorage(header).into())),
-                                        }
-                                    })
-                                    .and_then(move |post_checked_header: PostCheckedHeader| {
-                                        self6.apply_block(post_checked_header, &block)
-                                    })
-                                    .and_then(move |new_ref| {
-                                        branch
-                                            .clone()
-                                            .update_ref(new_ref)
-                                            .map(move |_old_ref| (branch, returned))
-                                            .map_err(|_: Infallible| unreachable!())
-                                    })
+                                self4.apply_stored_block(block).and_then(move |new_ref| {
+                                    branch
+                                        .clone()
+                                        .update_ref(new_ref)
+                                        .map(move |_old_ref| (branch, returned))
+                                        .map_err(|_: Infallible| unreachable!())
+                                })
                             })
                             .map(|(branch, _)| branch)
                     })
             })
     }
 
+    /// checks the header of a block read from the storage, whose parent
+    /// must have been loaded already, and applies the block
+    fn apply_stored_block(&self, block: Block) -> impl Future<Item = Arc<Ref>, Error = Error> {
+        let header = block.header.clone();
+
+        let mut self1 = self.clone();
+        let mut self2 = self.clone();
+        let mut self3 = self.clone();
+
+        self1
+            .pre_check_header(header, true)
+            .and_then(move |pre_checked_header| match pre_checked_header {
+                PreCheckedHeader::HeaderWithCache { header, parent_ref } => {
+                    future::Either::A(self2.post_check_header(header, parent_ref))
+                }
+                PreCheckedHeader::AlreadyPresent { header, .. } => {
+                    unreachable!(
+                        "block already present, this should not happen. {:#?}",
+                        header
+                    )
+                }
+                PreCheckedHeader::MissingParent { header } => future::Either::B(future::err(
+                    ErrorKind::MissingParentBlockFromStorage(header).into(),
+                )),
+            })
+            .and_then(move |post_checked_header| self3.apply_block(post_checked_header, &block))
+    }
+
+    /// returns a future replaying the blocks of the storage from the block0
+    /// to the `Head`, like `load_from_storage`, but stopping at the first
+    /// block failing to load instead of failing.
+    ///
+    /// # Errors
+    ///
+    /// The resulted future fails only if the block0 cannot be loaded (see
+    /// `load_from_storage`).
+    ///
+    pub fn verify_storage(
+        &mut self,
+        block0: Block,
+    ) -> impl Future<Item = StorageVerification, Error = Error> {
+        let block0_id = block0.header.hash();
+        let mut self1 = self.clone();
+        let self2 = self.clone();
+
+        self.storage
+            .block_exists(block0_id)
+            .map_err(|e| Error::with_chain(e, "Cannot check if block0 is in storage"))
+            .and_then(|existence| {
+                if !existence {
+                    future::err(ErrorKind::Block0NotAlreadyInStorage.into())
+                } else {
+                    future::ok(())
+                }
+            })
+            .and_then(move |()| self1.apply_block0(block0))
+            .and_then(|branch| branch.get_ref().map_err(|_: Infallible| unreachable!()))
+            .and_then(move |block0_ref| {
+                self2
+                    .storage
+                    .get_tag(MAIN_BRANCH_TAG.to_owned())
+                    .map_err(|e| Error::with_chain(e, "Cannot get hash of the HEAD tag"))
+                    .and_then(move |head| match head {
+                        None => future::Either::A(future::ok(StorageVerification {
+                            head: None,
+                            last_valid: block0_ref,
+                            error: Some(ErrorKind::NoTag(MAIN_BRANCH_TAG.to_owned()).into()),
+                        })),
+                        Some(head) => future::Either::B(self2.verify_blocks(block0_ref, head)),
+                    })
+            })
+    }
+
+    fn verify_blocks(
+        &self,
+        block0_ref: Arc<Ref>,
+        head: HeaderHash,
+    ) -> impl Future<Item = StorageVerification, Error = Error> {
+        let blockchain = self.clone();
+        let verification = move |last_valid, error| StorageVerification {
+            head: Some(head.clone()),
+            last_valid,
+            error,
+        };
+
+        self.storage
+            .stream_from_to(block0_ref.hash(), head)
+            .then(move |stream| match stream {
+                Err(e) => future::Either::A(future::ok(verification(
+                    block0_ref,
+                    Some(Error::with_chain(
+                        e,
+                        "Cannot iterate blocks from block0 to HEAD",
+                    )),
+                ))),
+                Ok(None) => future::Either::A(future::ok(verification(
+                    block0_ref,
+                    Some("HEAD is not a descendant of block0".into()),
+                ))),
+                Ok(Some(block_stream)) => future::Either::B(future::loop_fn(
+                    (block_stream, block0_ref),
+                    move |(block_stream, last_valid)| {
+                        let blockchain = blockchain.clone();
+                        let verification = verification.clone();
+                        block_stream.into_future().then(move |next| match next {
+                            Err((e, _)) => {
+                                future::Either::A(future::ok(future::Loop::Break(verification(
+                                    last_valid,
+                                    Some(Error::with_chain(e, "Cannot read a block from storage")),
+                                ))))
+                            }
+                            Ok((None, _)) => future::Either::A(future::ok(future::Loop::Break(
+                                verification(last_valid, None),
+                            ))),
+                            Ok((Some(block), block_stream)) => future::Either::B(
+                                blockchain.apply_stored_block(block).then(move |applied| {
+                                    Ok::<_, Error>(match applied {
+                                        Ok(new_ref) => {
+                                            future::Loop::Continue((block_stream, new_ref))
+                                        }
+                                        Err(e) => {
+                                            future::Loop::Break(verification(last_valid, Some(e)))
+                                        }
+                                    })
+                                }),
+                            ),
+                        })
+                    },
+                )),
+            })
+    }
+
     pub fn get_checkpoints(
         &self,
         branch: Branch,
@@ -1,4 +1,7 @@
+use crate::blockcfg::{BlockDate, ConsensusVersion, HeaderHash};
 use crate::blockchain::{Ref, Storage};
+use chain_storage::error::Error as StorageError;
+use tokio::prelude::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
 pub enum ComparisonResult {
@@ -6,6 +9,26 @@ pub enum ComparisonResult {
     PreferCandidate,
 }
 
+/// the rule used to decide between two branches forking deeper than the
+/// maximum rollback depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChainSelectionRule {
+    /// Ouroboros Praos: such a fork is never followed
+    LongestChain,
+    /// Ouroboros Genesis: the branch having the most blocks in a window
+    /// of slots right after the fork point is preferred
+    Density,
+}
+
+impl ChainSelectionRule {
+    fn for_consensus(consensus_version: ConsensusVersion) -> Self {
+        match consensus_version {
+            ConsensusVersion::Bft => ChainSelectionRule::LongestChain,
+            ConsensusVersion::GenesisPraos => ChainSelectionRule::Density,
+        }
+    }
+}
+
 /// chose which of the two Ref is the most interesting to keep as a branch
 ///
 /// i.e. if the two Ref points to the same block date: this allows to make a choice
@@ -13,24 +36,218 @@ pub enum ComparisonResult {
 /// on the same block date is to a requirement to call this function as it will still
 /// work to make a choice as to which of these two Ref is the right choice.
 ///
-pub fn compare_against(storage: &Storage, current: &Ref, candidate: &Ref) -> ComparisonResult {
-    let rollback_possible = check_rollback_up_to(
-        // missing max depth parameter from the protocol settings
-        storage, current, candidate,
-    );
+/// If switching to the candidate rolls back at most the epoch stability
+/// depth of the current branch, the longest of the two is preferred.
+/// Otherwise the choice depends on the consensus: with BFT the current
+/// branch is kept, with Genesis Praos the densest branch within the
+/// density window after the fork point is preferred (see `density_window`). This
+/// protects the nodes bootstrapping from scratch (or coming back after
+/// a long time offline) against long range attacks.
+///
+pub fn compare_against(
+    storage: &Storage,
+    current: &Ref,
+    candidate: &Ref,
+) -> impl Future<Item = ComparisonResult, Error = StorageError> {
+    let max_rollback_depth = current.epoch_stability_depth();
+    let rule = ChainSelectionRule::for_consensus(current.ledger().consensus_version());
+    let storage = storage.clone();
+    let current = current.clone();
+    let candidate = candidate.clone();
+
+    storage
+        .is_within_rollback_depth(current.hash(), candidate.hash(), max_rollback_depth.into())
+        .and_then(move |rollback_possible| {
+            if rollback_possible {
+                future::Either::A(future::ok(longest_chain(&current, &candidate)))
+            } else {
+                match rule {
+                    ChainSelectionRule::LongestChain => {
+                        future::Either::A(future::ok(ComparisonResult::PreferCurrent))
+                    }
+                    ChainSelectionRule::Density => {
+                        let slots_per_epoch =
+                            current.epoch_leadership_schedule().era().slots_per_epoch();
+                        future::Either::B(densest_chain(
+                            storage,
+                            slots_per_epoch,
+                            density_window(max_rollback_depth, slots_per_epoch),
+                            current.hash(),
+                            candidate.hash(),
+                        ))
+                    }
+                }
+            }
+        })
+}
 
-    if rollback_possible && current.chain_length() < candidate.chain_length() {
+fn longest_chain(current: &Ref, candidate: &Ref) -> ComparisonResult {
+    if current.chain_length() < candidate.chain_length() {
         ComparisonResult::PreferCandidate
     } else {
         ComparisonResult::PreferCurrent
     }
 }
 
-fn check_rollback_up_to(
-    // TODO: missing max depth parameter
-    _: &Storage,
-    _ref1: &Ref,
-    _ref2: &Ref,
-) -> bool {
-    true
+/// Number of slots after the fork point in which the density of the two
+/// branches is compared, given the epoch stability depth `k`.
+///
+/// Ouroboros Genesis compares the branches over the stability window of
+/// `3k/f` slots, `f` being the active slot coefficient. With epochs of the
+/// `10k/f` slots of the Praos parameters, this is 3/10 of an epoch.
+/// It is never shorter than `k` slots, as a slot holds at most one block.
+fn density_window(k: u32, slots_per_epoch: u32) -> u32 {
+    let stability_window = u64::from(slots_per_epoch) * 3 / 10;
+    std::cmp::max(stability_window as u32, k)
+}
+
+fn densest_chain(
+    storage: Storage,
+    slots_per_epoch: u32,
+    window: u32,
+    current: HeaderHash,
+    candidate: HeaderHash,
+) -> impl Future<Item = ComparisonResult, Error = StorageError> {
+    storage
+        .find_common_ancestor(current, candidate)
+        .and_then({
+            let storage = storage.clone();
+            move |fork_point| {
+                storage
+                    .get(fork_point)
+                    .and_then(|block| block.ok_or(StorageError::BlockNotFound))
+                    .map(move |block| (fork_point, block.header.block_date()))
+            }
+        })
+        .and_then(move |(fork_point, fork_date)| {
+            let until = date_after(fork_date, window, slots_per_epoch);
+            storage
+                .count_blocks_after(current, fork_point, until)
+                .join(storage.count_blocks_after(candidate, fork_point, until))
+        })
+        .map(|(current_density, candidate_density)| {
+            if current_density < candidate_density {
+                ComparisonResult::PreferCandidate
+            } else {
+                ComparisonResult::PreferCurrent
+            }
+        })
+}
+
+fn date_after(date: BlockDate, slots: u32, slots_per_epoch: u32) -> BlockDate {
+    let slot = u64::from(date.epoch) * u64::from(slots_per_epoch)
+        + u64::from(date.slot_id)
+        + u64::from(slots);
+    BlockDate {
+        epoch: (slot / u64::from(slots_per_epoch)) as u32,
+        slot_id: (slot % u64::from(slots_per_epoch)) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{densest_chain, density_window, ComparisonResult};
+    use crate::blockchain::{testing::ChainBuilder, Storage};
+    use chain_storage::memory::MemoryBlockStore;
+    use tokio::prelude::*;
+
+    const SLOTS_PER_EPOCH: u32 = 100;
+
+    fn storage(chain: &ChainBuilder) -> Storage {
+        let mut storage = Storage::new(Box::new(MemoryBlockStore::new()));
+        storage.put_block(chain.block0().clone()).wait().unwrap();
+        storage
+    }
+
+    #[test]
+    fn density_window_is_in_slots() {
+        assert_eq!(density_window(10, 100), 30);
+        assert_eq!(density_window(50, 100), 50);
+        assert_eq!(density_window(2160, 21600), 6480);
+    }
+
+    #[test]
+    fn densest_branch_is_preferred_over_the_longest() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        let fork_point = chain.branch(&chain.block0().header, 1..=5);
+        let fork_header = &fork_point[4].header;
+        // one block every 10 slots, longer but sparse after the fork point
+        let sparse = chain.branch(fork_header, (1..=40).map(|i| 5 + i * 10));
+        // one block per slot, shorter but dense after the fork point
+        let dense = chain.branch(fork_header, 6..=30);
+        storage.put_blocks(fork_point).wait().unwrap();
+        storage.put_blocks(sparse.clone()).wait().unwrap();
+        storage.put_blocks(dense.clone()).wait().unwrap();
+
+        let sparse_tip = sparse.last().unwrap().header.hash();
+        let dense_tip = dense.last().unwrap().header.hash();
+        let compare = |current, candidate| {
+            densest_chain(storage.clone(), SLOTS_PER_EPOCH, 20, current, candidate)
+                .wait()
+                .unwrap()
+        };
+        assert_eq!(
+            compare(sparse_tip, dense_tip),
+            ComparisonResult::PreferCandidate
+        );
+        assert_eq!(
+            compare(dense_tip, sparse_tip),
+            ComparisonResult::PreferCurrent
+        );
+    }
+
+    #[test]
+    fn equally_dense_branches_keep_the_current_one() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        let block0 = &chain.block0().header;
+        // 20 blocks each in the window, the blocks past the window differ
+        let short = chain.branch(block0, 1..=20);
+        let long = chain.branch(block0, (2..=21).chain(30..=80));
+        storage.put_blocks(short.clone()).wait().unwrap();
+        storage.put_blocks(long.clone()).wait().unwrap();
+
+        let short_tip = short.last().unwrap().header.hash();
+        let long_tip = long.last().unwrap().header.hash();
+        let compare = |current, candidate| {
+            densest_chain(storage.clone(), SLOTS_PER_EPOCH, 25, current, candidate)
+                .wait()
+                .unwrap()
+        };
+        assert_eq!(
+            compare(short_tip, long_tip),
+            ComparisonResult::PreferCurrent
+        );
+        assert_eq!(
+            compare(long_tip, short_tip),
+            ComparisonResult::PreferCurrent
+        );
+    }
+
+    #[test]
+    fn density_window_spans_epochs() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        // forks 5 slots before the end of the first epoch
+        let fork_point = chain.branch(&chain.block0().header, 1..=95);
+        let fork_header = &fork_point[94].header;
+        // all its blocks are in the next epoch, within the window
+        let current = chain.branch(fork_header, 100..=110);
+        let candidate = chain.branch(fork_header, 96..=100);
+        storage.put_blocks(fork_point).wait().unwrap();
+        storage.put_blocks(current.clone()).wait().unwrap();
+        storage.put_blocks(candidate.clone()).wait().unwrap();
+
+        let result = densest_chain(
+            storage,
+            SLOTS_PER_EPOCH,
+            20,
+            candidate.last().unwrap().header.hash(),
+            current.last().unwrap().header.hash(),
+        )
+        .wait()
+        .unwrap();
+        assert_eq!(result, ComparisonResult::PreferCandidate);
+    }
 }
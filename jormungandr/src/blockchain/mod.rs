@@ -7,11 +7,13 @@ mod process;
 mod reference;
 mod reference_cache;
 mod storage;
+#[cfg(test)]
+pub mod testing;
 mod tip;
 
 pub use self::{
     branch::{Branch, Branches},
-    chain::{Blockchain, Error, ErrorKind, PreCheckedHeader, MAIN_BRANCH_TAG},
+    chain::{Blockchain, Error, ErrorKind, PreCheckedHeader, StorageVerification, MAIN_BRANCH_TAG},
     chain_selection::{compare_against, ComparisonResult},
     checkpoints::Checkpoints,
     multiverse::Multiverse,
@@ -6,7 +6,7 @@ use crate::{
     blockcfg::{Block, Epoch, FragmentId, Header, HeaderHash},
     intercom::{self, BlockMsg, ExplorerMsg, NetworkMsg, PropagateMsg, TransactionMsg},
     leadership::NewEpochToSchedule,
-    network::p2p::topology::NodeId,
+    network::p2p::{comm::Misbehavior, topology::NodeId},
     stats_counter::StatsCounter,
     utils::{
         async_msg::MessageBox,
@@ -21,7 +21,7 @@ use futures::future::Either;
 use slog::Logger;
 use tokio::{prelude::*, sync::mpsc::Sender};
 
-use std::{convert::identity, sync::Arc};
+use std::{convert::identity, sync::Arc, time::Instant};
 
 error_chain! {
     links {
@@ -95,6 +95,7 @@ pub fn run_handle_input(
             });
         }
         BlockMsg::LeadershipBlock(block) => {
+            let started = Instant::now();
             let logger = info.logger().new(o!(
                 "hash" => block.header.hash().to_string(),
                 "parent" => block.header.parent_id().to_string(),
@@ -132,6 +133,7 @@ pub fn run_handle_input(
             };
 
             stats_counter.add_block_recv_cnt(1);
+            stats_counter.add_block_process_time(started.elapsed());
         }
         BlockMsg::AnnouncedBlock(header, node_id) => {
             let future = process_block_announcement(
@@ -144,7 +146,8 @@ pub fn run_handle_input(
             );
             future.wait().unwrap();
         }
-        BlockMsg::NetworkBlock(block, reply) => {
+        BlockMsg::NetworkBlock(block, node_id, reply) => {
+            let started = Instant::now();
             let fragment_ids = block.fragments().map(|f| f.id()).collect::<Vec<_>>();
             let logger = info.logger().new(o!(
                 "hash" => block.header.hash().to_string(),
@@ -154,6 +157,16 @@ pub fn run_handle_input(
             let future = process_network_block(blockchain.clone(), block.clone(), logger.clone());
             match future.wait() {
                 Err(e) => {
+                    if let Some(node_id) = node_id.filter(|_| is_invalid_block_error(&e)) {
+                        network_msg_box
+                            .try_send(NetworkMsg::ReportMisbehavior(
+                                node_id,
+                                Misbehavior::InvalidBlock,
+                            ))
+                            .unwrap_or_else(|err| {
+                                error!(logger, "cannot report peer to network: {}", err)
+                            });
+                    }
                     reply.reply_error(network_block_error_into_reply(e));
                 }
                 Ok(maybe_updated) => {
@@ -188,6 +201,7 @@ pub fn run_handle_input(
                     reply.reply_ok(());
                 }
             }
+            stats_counter.add_block_process_time(started.elapsed());
         }
         BlockMsg::ChainHeaders(_stream) => unimplemented!(),
     };
@@ -230,28 +244,34 @@ pub fn process_new_ref(
 
     tip.clone()
         .get_ref()
+        .map_err(|_: std::convert::Infallible| unreachable!())
         .and_then(move |tip_ref| {
             if tip_ref.hash() == candidate.block_parent_hash() {
                 info!(logger, "update current branch tip");
-                A(A(tip.update_ref(candidate).map(|_| true)))
+                A(tip
+                    .update_ref(candidate)
+                    .map(|_| true)
+                    .map_err(|_: std::convert::Infallible| unreachable!()))
             } else {
-                match compare_against(blockchain.storage(), &tip_ref, &candidate) {
-                    ComparisonResult::PreferCurrent => {
-                        info!(logger, "create new branch");
-                        A(B(future::ok(false)))
-                    }
-                    ComparisonResult::PreferCandidate => {
-                        info!(logger, "switching to new candidate branch");
-                        B(blockchain
-                            .branches_mut()
-                            .apply_or_create(candidate)
-                            .and_then(move |branch| tip.swap(branch))
-                            .map(|()| true))
-                    }
-                }
+                B(compare_against(blockchain.storage(), &tip_ref, &candidate)
+                    .map_err(|e| Error::with_chain(e, "Cannot compare the candidate branch"))
+                    .and_then(move |comparison| match comparison {
+                        ComparisonResult::PreferCurrent => {
+                            info!(logger, "create new branch");
+                            A(future::ok(false))
+                        }
+                        ComparisonResult::PreferCandidate => {
+                            info!(logger, "switching to new candidate branch");
+                            B(blockchain
+                                .branches_mut()
+                                .apply_or_create(candidate)
+                                .and_then(move |branch| tip.swap(branch))
+                                .map(|()| true)
+                                .map_err(|_: std::convert::Infallible| unreachable!()))
+                        }
+                    }))
             }
         })
-        .map_err(|_: std::convert::Infallible| unreachable!())
         .and_then(move |tip_updated| {
             if tip_updated {
                 A(storage
@@ -266,7 +286,7 @@ pub fn process_new_ref(
 pub fn handle_end_of_epoch(
     logger: Logger,
     new_epoch_announcements: Sender<NewEpochToSchedule>,
-    mut blockchain: Blockchain,
+    blockchain: Blockchain,
     blockchain_tip: Tip,
     epoch: Epoch,
 ) -> impl Future<Item = (), Error = Error> {
@@ -275,6 +295,16 @@ pub fn handle_end_of_epoch(
         .get_ref()
         .map_err(|_: std::convert::Infallible| unreachable!())
         .and_then(move |ref_tip| {
+            blockchain
+                .limit_served_blocks(&ref_tip)
+                .map_err(|e| Error::with_chain(e, "Cannot limit the served blocks"))
+                .map(move |unserved| (blockchain, ref_tip, unserved))
+        })
+        .and_then(move |(mut blockchain, ref_tip, unserved)| {
+            if let Some(chain_length) = unserved {
+                info!(logger, "stopped serving the old blocks" ; "up_to_chain_length" => u32::from(chain_length));
+            }
+
             let (new_schedule, new_parameters, time_frame, _) =
                 blockchain.new_epoch_leadership_from(epoch, ref_tip);
 
@@ -420,6 +450,17 @@ pub fn process_network_block(
         })
 }
 
+/// the block was rejected for being invalid, rather than because
+/// it cannot be processed by this node at this time
+fn is_invalid_block_error(err: &chain::Error) -> bool {
+    use super::chain::ErrorKind::*;
+
+    match err.0 {
+        Ledger(_) | BlockHeaderVerificationFailed(_) | CannotApplyBlock => true,
+        _ => false,
+    }
+}
+
 fn network_block_error_into_reply(err: chain::Error) -> intercom::Error {
     use super::chain::ErrorKind::*;
 
@@ -108,6 +108,14 @@ impl Ref {
         &self.epoch_ledger_parameters
     }
 
+    /// maximum number of blocks this branch can be rolled back to switch
+    /// to another branch. This is the `epoch_stability_depth` of the
+    /// ledger settings: the one set in the block0 initial settings or by
+    /// a later update, the ledger's own default otherwise.
+    pub fn epoch_stability_depth(&self) -> u32 {
+        self.ledger.settings().epoch_stability_depth
+    }
+
     pub fn last_ref_previous_epoch(&self) -> Option<&Arc<Ref>> {
         self.previous_epoch_state.as_ref()
     }
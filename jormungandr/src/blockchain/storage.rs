@@ -1,18 +1,39 @@
 use crate::{
-    blockcfg::{Block, HeaderHash},
+    blockcfg::{Block, BlockDate, ChainLength, Header, HeaderHash},
     start_up::NodeStorage,
 };
 use chain_storage::{
     error::Error as StorageError,
     store::{for_path_to_nth_ancestor, BlockInfo, BlockStore},
 };
-use std::ops::Deref as _;
+use std::{
+    io,
+    ops::Deref as _,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
 use tokio::prelude::*;
 use tokio::sync::lock::{Lock, LockGuard};
 
+/// tag of the most recent block no longer served to the other nodes
+const UNSERVED_TAG: &str = "UNSERVED";
+
 #[derive(Clone)]
 pub struct Storage {
     inner: Lock<NodeStorage>,
+    /// chain length of the most recent block no longer served, 0 if none
+    unserved_up_to: Arc<AtomicU32>,
+    /// the last ancestor a tip can be rolled back to, for the last tip
+    /// candidates were compared with
+    fork_limit: Arc<Mutex<Option<ForkLimit>>>,
+}
+
+struct ForkLimit {
+    tip: HeaderHash,
+    depth: u64,
+    block_hash: HeaderHash,
 }
 
 pub struct BlockStream {
@@ -23,12 +44,56 @@ pub struct BlockStream {
 }
 
 impl Storage {
+    /// The blocks no longer served by a previous run of the node stay so.
+    /// If the tagged block cannot be read, every block is served until the
+    /// served blocks are limited again.
     pub fn new(storage: NodeStorage) -> Self {
+        let unserved_up_to = match storage.get_tag(UNSERVED_TAG) {
+            Ok(Some(block_hash)) => storage
+                .get_block(&block_hash)
+                .map(|(block, _)| u32::from(block.header.chain_length()))
+                .unwrap_or(0),
+            Ok(None) | Err(_) => 0,
+        };
         Storage {
             inner: Lock::new(storage),
+            unserved_up_to: Arc::new(AtomicU32::new(unserved_up_to)),
+            fork_limit: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Stops serving the ancestors of `tip` up to the given chain length,
+    /// except block0. The most recent block no longer served is tagged, the
+    /// blocks stay unserved after a restart.
+    ///
+    /// The block store does not support removing blocks, so the unserved
+    /// blocks are still kept on disk and their space is not reclaimed;
+    /// they are only no longer sent to the other nodes.
+    pub fn stop_serving_up_to(
+        &self,
+        tip: HeaderHash,
+        tip_chain_length: ChainLength,
+        chain_length: ChainLength,
+    ) -> impl Future<Item = (), Error = StorageError> {
+        let mut inner = self.inner.clone();
+        let unserved_up_to = self.unserved_up_to.clone();
+        let distance = u32::from(tip_chain_length) - u32::from(chain_length);
+
+        future::poll_fn(move || Ok(inner.poll_lock())).and_then(move |mut store| {
+            let last_unserved = for_path_to_nth_ancestor(&*store, &tip, distance.into(), |_| {})?;
+            store.put_tag(UNSERVED_TAG, &last_unserved.block_hash)?;
+            unserved_up_to.store(u32::from(chain_length), Ordering::Relaxed);
+            Ok(())
+        })
+    }
+
+    /// Returns true if the block with the given chain length is served to
+    /// the other nodes.
+    pub fn is_served(&self, chain_length: ChainLength) -> bool {
+        let chain_length = u32::from(chain_length);
+        chain_length == 0 || chain_length > self.unserved_up_to.load(Ordering::Relaxed)
+    }
+
     #[deprecated(since = "new blockchain API", note = "use the stream iterator instead")]
     pub fn get_inner(&self) -> impl Future<Item = LockGuard<NodeStorage>, Error = StorageError> {
         let mut inner = self.inner.clone();
@@ -122,6 +187,49 @@ impl Storage {
         })
     }
 
+    /// Stores the blocks in order, taking the lock once for the whole
+    /// chunk instead of once per block. The blocks already in the storage
+    /// are skipped, the headers of the newly stored ones are returned.
+    ///
+    /// The block store has no transaction API: if a write fails, the
+    /// blocks preceding the failing one remain stored.
+    pub fn put_blocks(
+        &mut self,
+        blocks: Vec<Block>,
+    ) -> impl Future<Item = Vec<Header>, Error = StorageError> {
+        let mut inner = self.inner.clone();
+
+        future::poll_fn(move || Ok(inner.poll_lock()))
+            .and_then(move |mut guard| put_blocks(&mut *guard, blocks))
+    }
+
+    /// Stores the blocks like `put_blocks`, then moves the tag to the last
+    /// one while still holding the lock: the tag is only moved once all
+    /// the blocks of the chunk are stored, and no other write comes in
+    /// between. The chunk must not be empty.
+    ///
+    /// This is not a backend transaction, which the block store API does
+    /// not provide: if a write fails, the blocks stored before it remain,
+    /// past the tag, and are skipped when the chunk is stored again.
+    pub fn put_blocks_and_tag(
+        &mut self,
+        blocks: Vec<Block>,
+        tag: String,
+    ) -> impl Future<Item = Vec<Header>, Error = StorageError> {
+        let mut inner = self.inner.clone();
+        let last_hash = blocks
+            .last()
+            .expect("chunks of blocks are not empty")
+            .header
+            .hash();
+
+        future::poll_fn(move || Ok(inner.poll_lock())).and_then(move |mut guard| {
+            let stored = put_blocks(&mut *guard, blocks)?;
+            guard.put_tag(&tag, &last_hash)?;
+            Ok(stored)
+        })
+    }
+
     /// Return values:
     /// - `Ok(Some(stream))` - `from` is ancestor of `to`, returns blocks between them
     /// - `Ok(None)` - `from` is not ancestor of `to`
@@ -151,6 +259,110 @@ impl Storage {
         })
     }
 
+    /// Returns true if `candidate` descends from the ancestor of `tip`
+    /// `depth` blocks back, or from block0 if `tip` is less than `depth`
+    /// blocks away from it. That is, switching from `tip` to `candidate`
+    /// rolls back at most `depth` blocks.
+    ///
+    /// The ancestor of the tip is only searched once per tip and depth,
+    /// the candidates competing with the same tip reuse it.
+    pub fn is_within_rollback_depth(
+        &self,
+        tip: HeaderHash,
+        candidate: HeaderHash,
+        depth: u64,
+    ) -> impl Future<Item = bool, Error = StorageError> {
+        let mut inner = self.inner.clone();
+        let fork_limit = self.fork_limit.clone();
+        future::poll_fn(move || Ok(inner.poll_lock())).and_then(move |store| {
+            let mut fork_limit = fork_limit.lock().unwrap();
+            let fork_limit_hash = match &*fork_limit {
+                Some(limit) if limit.tip == tip && limit.depth == depth => limit.block_hash,
+                _ => {
+                    let tip_info = store.get_block_info(&tip)?;
+                    if tip_info.depth == 0 {
+                        return Err(StorageError::BackendError(Box::new(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("block {} is stored with a depth of 0", tip),
+                        ))));
+                    }
+                    let distance = std::cmp::min(depth, tip_info.depth - 1);
+                    let block_hash =
+                        for_path_to_nth_ancestor(&*store, &tip, distance, |_| {})?.block_hash;
+                    *fork_limit = Some(ForkLimit {
+                        tip,
+                        depth,
+                        block_hash,
+                    });
+                    block_hash
+                }
+            };
+            let is_ancestor = store.is_ancestor(&fork_limit_hash, &candidate)?;
+            Ok(is_ancestor.is_some())
+        })
+    }
+
+    /// Returns the hash of the most recent block which is an ancestor
+    /// of both `a` and `b` (or is one of them).
+    pub fn find_common_ancestor(
+        &self,
+        a: HeaderHash,
+        b: HeaderHash,
+    ) -> impl Future<Item = HeaderHash, Error = StorageError> {
+        let mut inner = self.inner.clone();
+        future::poll_fn(move || Ok(inner.poll_lock())).and_then(move |store| {
+            let mut a_info = store.get_block_info(&a)?;
+            let mut b_info = store.get_block_info(&b)?;
+            while a_info.block_hash != b_info.block_hash {
+                if a_info.depth >= b_info.depth {
+                    a_info = store.get_block_info(&a_info.parent_id())?;
+                } else {
+                    b_info = store.get_block_info(&b_info.parent_id())?;
+                }
+            }
+            Ok(a_info.block_hash)
+        })
+    }
+
+    /// Counts the blocks of the branch ending with `tip` which come after
+    /// its ancestor `ancestor` and are dated no later than `until`.
+    ///
+    /// The dates grow along the branch, so the last block dated no later
+    /// than `until` is searched by bisection over the block infos: only
+    /// the blocks of the bisection steps are loaded to read their date.
+    pub fn count_blocks_after(
+        &self,
+        tip: HeaderHash,
+        ancestor: HeaderHash,
+        until: BlockDate,
+    ) -> impl Future<Item = u64, Error = StorageError> {
+        let mut inner = self.inner.clone();
+        future::poll_fn(move || Ok(inner.poll_lock())).and_then(move |store| {
+            let tip_depth = store.get_block_info(&tip)?.depth;
+            let ancestor_depth = store.get_block_info(&ancestor)?.depth;
+            // the first `low` blocks after the ancestor are dated no later
+            // than `until`, the ones after the first `high` are not
+            let mut low = 0;
+            let mut high = tip_depth - ancestor_depth;
+            while low < high {
+                let middle = low + (high - low + 1) / 2;
+                let block_info = for_path_to_nth_ancestor(
+                    &*store,
+                    &tip,
+                    tip_depth - ancestor_depth - middle,
+                    |_| {},
+                )?;
+                let (block, _) = store.get_block(&block_info.block_hash)?;
+                if block.header.block_date() <= until {
+                    low = middle;
+                } else {
+                    high = middle - 1;
+                }
+            }
+            Ok(low)
+        })
+    }
+
     pub fn get_checkpoints(
         &self,
         tip: HeaderHash,
@@ -168,6 +380,19 @@ impl Storage {
     }
 }
 
+fn put_blocks(store: &mut NodeStorage, blocks: Vec<Block>) -> Result<Vec<Header>, StorageError> {
+    let mut stored = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        match store.put_block(&block) {
+            Err(StorageError::BlockNotFound) => unreachable!(),
+            Err(StorageError::BlockAlreadyPresent) => {}
+            Err(error) => return Err(error),
+            Ok(()) => stored.push(block.header),
+        }
+    }
+    Ok(stored)
+}
+
 impl Stream for BlockStream {
     type Item = Block;
     type Error = StorageError;
@@ -208,3 +433,135 @@ impl Stream for BlockStream {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Storage, UNSERVED_TAG};
+    use crate::blockchain::testing::ChainBuilder;
+    use chain_storage::{memory::MemoryBlockStore, store::BlockStore};
+    use tokio::prelude::*;
+
+    const SLOTS_PER_EPOCH: u32 = 100;
+
+    fn storage(chain: &ChainBuilder) -> Storage {
+        let mut storage = Storage::new(Box::new(MemoryBlockStore::new()));
+        storage.put_block(chain.block0().clone()).wait().unwrap();
+        storage
+    }
+
+    #[test]
+    fn rollback_within_depth_is_allowed() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        let main = chain.branch(&chain.block0().header, 1..=10);
+        // forks from the 7th block, 3 blocks behind the tip
+        let fork = chain.branch(&main[6].header, 11..=13);
+        storage.put_blocks(main.clone()).wait().unwrap();
+        storage.put_blocks(fork.clone()).wait().unwrap();
+
+        let tip = main[9].header.hash();
+        let candidate = fork[2].header.hash();
+        assert!(storage
+            .is_within_rollback_depth(tip, candidate, 3)
+            .wait()
+            .unwrap());
+        assert!(storage
+            .is_within_rollback_depth(tip, candidate, 100)
+            .wait()
+            .unwrap());
+    }
+
+    #[test]
+    fn rollback_deeper_than_depth_is_rejected() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        let main = chain.branch(&chain.block0().header, 1..=10);
+        let fork = chain.branch(&main[6].header, 11..=13);
+        storage.put_blocks(main.clone()).wait().unwrap();
+        storage.put_blocks(fork.clone()).wait().unwrap();
+
+        let tip = main[9].header.hash();
+        assert!(!storage
+            .is_within_rollback_depth(tip, fork[2].header.hash(), 2)
+            .wait()
+            .unwrap());
+
+        // a fork from the block0 rolls back the whole main branch
+        let fork = chain.branch(&chain.block0().header, 11..=30);
+        storage.put_blocks(fork.clone()).wait().unwrap();
+        assert!(!storage
+            .is_within_rollback_depth(tip, fork[19].header.hash(), 9)
+            .wait()
+            .unwrap());
+        assert!(storage
+            .is_within_rollback_depth(tip, fork[19].header.hash(), 10)
+            .wait()
+            .unwrap());
+    }
+
+    #[test]
+    fn unserved_blocks_are_restored_from_the_storage() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        let blocks = chain.branch(&chain.block0().header, 1..=10);
+        storage.put_blocks(blocks.clone()).wait().unwrap();
+
+        let tip = &blocks[9].header;
+        storage
+            .stop_serving_up_to(
+                tip.hash(),
+                tip.chain_length(),
+                blocks[3].header.chain_length(),
+            )
+            .wait()
+            .unwrap();
+        assert!(!storage.is_served(blocks[3].header.chain_length()));
+        assert!(storage.is_served(blocks[4].header.chain_length()));
+        let tag = storage.get_tag(UNSERVED_TAG.to_owned()).wait().unwrap();
+        assert_eq!(tag, Some(blocks[3].header.hash()));
+
+        // a store with the tag, as found by the node on restart
+        let mut store = MemoryBlockStore::new();
+        store.put_block(chain.block0()).unwrap();
+        for block in &blocks {
+            store.put_block(block).unwrap();
+        }
+        store
+            .put_tag(UNSERVED_TAG, &blocks[3].header.hash())
+            .unwrap();
+        let storage = Storage::new(Box::new(store));
+        assert!(storage.is_served(chain.block0().header.chain_length()));
+        assert!(!storage.is_served(blocks[3].header.chain_length()));
+        assert!(storage.is_served(blocks[4].header.chain_length()));
+    }
+
+    #[test]
+    fn chunk_is_stored_with_its_tag() {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let mut storage = storage(&chain);
+        let blocks = chain.branch(&chain.block0().header, 1..=10);
+
+        let stored = storage
+            .put_blocks_and_tag(blocks[..6].to_vec(), "HEAD".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(stored.len(), 6);
+        // the blocks already stored are skipped
+        let stored = storage
+            .put_blocks_and_tag(blocks[4..].to_vec(), "HEAD".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            stored
+                .iter()
+                .map(|header| header.hash())
+                .collect::<Vec<_>>(),
+            blocks[6..]
+                .iter()
+                .map(|block| block.header.hash())
+                .collect::<Vec<_>>()
+        );
+        let tag = storage.get_tag("HEAD".to_owned()).wait().unwrap();
+        assert_eq!(tag, Some(blocks[9].header.hash()));
+    }
+}
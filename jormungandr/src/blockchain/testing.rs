@@ -0,0 +1,87 @@
+//! blocks of a BFT blockchain with a single leader, to build the chains
+//! and forks used by the tests and the benchmarks
+
+use crate::blockcfg::{Block, BlockDate, BlockVersion, ContentsBuilder, Header, HeaderBuilderNew};
+use chain_addr::Discrimination;
+use chain_core::property::ChainLength as _;
+use chain_crypto::{Ed25519, SecretKey};
+use chain_impl_mockchain::{block::ConsensusVersion, fee::LinearFee, leadership::bft::LeaderId};
+use jormungandr_lib::interfaces::{
+    Block0Configuration, BlockchainConfiguration, ConsensusLeaderId, NumberOfSlotsPerEpoch,
+};
+
+pub struct ChainBuilder {
+    leader_key: SecretKey<Ed25519>,
+    leader_id: LeaderId,
+    slots_per_epoch: u32,
+    block0: Block,
+}
+
+impl ChainBuilder {
+    pub fn new(slots_per_epoch: u32) -> Self {
+        let leader_key = SecretKey::<Ed25519>::generate(&mut rand::thread_rng());
+        let leader_id = LeaderId::from(leader_key.to_public());
+        let mut blockchain_configuration = BlockchainConfiguration::new(
+            Discrimination::Test,
+            ConsensusVersion::Bft,
+            LinearFee::new(0, 0, 0),
+        );
+        blockchain_configuration.slots_per_epoch =
+            NumberOfSlotsPerEpoch::new(slots_per_epoch).expect("valid number of slots per epoch");
+        blockchain_configuration
+            .consensus_leader_ids
+            .push(ConsensusLeaderId(leader_id.clone()));
+        let block0 = Block0Configuration {
+            blockchain_configuration,
+            initial: Vec::new(),
+        }
+        .to_block();
+
+        ChainBuilder {
+            leader_key,
+            leader_id,
+            slots_per_epoch,
+            block0,
+        }
+    }
+
+    pub fn block0(&self) -> &Block {
+        &self.block0
+    }
+
+    /// the block of the leader following `parent` at the given date
+    pub fn block(&self, parent: &Header, date: BlockDate) -> Block {
+        let contents = ContentsBuilder::new().into();
+        let builder = HeaderBuilderNew::new(BlockVersion::Ed25519Signed, &contents)
+            .set_parent(&parent.hash(), parent.chain_length().next())
+            .set_date(date)
+            .to_bft_builder()
+            .unwrap()
+            .set_consensus_data(&self.leader_id);
+        let signature = self.leader_key.sign_slice(builder.get_authenticated_data());
+        let header = builder.set_signature(signature.into()).generalize();
+        Block { header, contents }
+    }
+
+    /// the blocks following `parent`, one in each of the given slots,
+    /// counted from the start of the blockchain
+    pub fn branch(&self, parent: &Header, slots: impl IntoIterator<Item = u32>) -> Vec<Block> {
+        let mut parent = parent.clone();
+        slots
+            .into_iter()
+            .map(|slot| {
+                let block = self.block(&parent, self.date(slot));
+                parent = block.header.clone();
+                block
+            })
+            .collect()
+    }
+
+    /// the date of the given slot, counted from the start of the blockchain
+    pub fn date(&self, slot: u32) -> BlockDate {
+        BlockDate {
+            epoch: slot / self.slots_per_epoch,
+            slot_id: slot % self.slots_per_epoch,
+        }
+    }
+}
@@ -1,15 +1,24 @@
-use crate::blockchain::{Branch, Ref};
+use crate::{
+    blockcfg::Header,
+    blockchain::{Branch, Ref},
+    utils::broadcast::Broadcaster,
+};
+use futures::sync::mpsc::Receiver;
 use std::{convert::Infallible, sync::Arc};
 use tokio::prelude::*;
 
 #[derive(Clone)]
 pub struct Tip {
     branch: Branch,
+    updates: Broadcaster<Header>,
 }
 
 impl Tip {
     pub fn new(branch: Branch) -> Self {
-        Tip { branch }
+        Tip {
+            branch,
+            updates: Broadcaster::new(),
+        }
     }
 
     pub fn get_ref(&self) -> impl Future<Item = Arc<Ref>, Error = Infallible> {
@@ -20,19 +29,32 @@ impl Tip {
         &mut self,
         new_ref: Arc<Ref>,
     ) -> impl Future<Item = Arc<Ref>, Error = Infallible> {
-        self.branch.update_ref(new_ref)
+        let updates = self.updates.clone();
+        let header = new_ref.header().clone();
+        self.branch
+            .update_ref(new_ref)
+            .inspect(move |_| updates.send(header))
     }
 
     pub fn swap(&mut self, mut branch: Branch) -> impl Future<Item = (), Error = Infallible> {
         let mut tip_branch = self.branch.clone();
+        let updates = self.updates.clone();
         self.branch()
             .get_ref()
             .and_then(move |tr| branch.update_ref(tr))
-            .and_then(move |br| tip_branch.update_ref(br))
-            .map(|_| ())
+            .and_then(move |br| {
+                let header = br.header().clone();
+                tip_branch.update_ref(br).map(move |_| updates.send(header))
+            })
     }
 
     pub fn branch(&self) -> &Branch {
         &self.branch
     }
+
+    /// Returns the stream of the headers of the blocks becoming the tip
+    /// from now on.
+    pub fn subscribe(&self) -> Receiver<Header> {
+        self.updates.subscribe()
+    }
 }
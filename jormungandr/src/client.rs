@@ -123,6 +123,19 @@ fn handle_get_headers_range(
     Ok(())
 }
 
+/// Fails with an "unavailable" error if the block is no longer served to
+/// the other nodes, so the peer can ask a node serving the full history.
+fn check_served(storage: &Storage, block: &Block) -> Result<(), Error> {
+    if !storage.is_served(block.header.chain_length()) {
+        Err(Error::unavailable(format!(
+            "block {} is no longer served by this node",
+            block.header.hash()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
 fn handle_get_blocks_range(
     storage: &Storage,
     from: HeaderHash,
@@ -130,13 +143,14 @@ fn handle_get_blocks_range(
     reply: &mut ReplyStreamHandle<Block>,
 ) -> Result<(), Error> {
     // FIXME: remove double locking
-    let storage = storage.get_inner().wait().unwrap();
+    let guard = storage.get_inner().wait().unwrap();
 
     // FIXME: include the from block
 
-    for x in store::iterate_range(&*storage, &from, &to)? {
+    for x in store::iterate_range(&*guard, &from, &to)? {
         let info = x?;
-        let (blk, _) = storage.get_block(&info.block_hash)?;
+        let (blk, _) = guard.get_block(&info.block_hash)?;
+        check_served(storage, &blk)?;
         if let Err(_) = reply.send(blk) {
             break;
         }
@@ -152,6 +166,7 @@ fn handle_get_blocks(
 ) -> Result<(), Error> {
     for id in ids.into_iter() {
         if let Some(blk) = storage.get(id).wait()? {
+            check_served(storage, &blk)?;
             if let Err(_) = reply.send(blk) {
                 break;
             }
@@ -192,10 +207,11 @@ fn handle_pull_blocks_to_tip(
 
     let tip = blockchain_tip.get_ref().wait().unwrap();
 
-    let storage = storage.get_inner().wait().unwrap();
-    for x in store::iterate_range(&*storage, &from, &tip.hash())? {
+    let guard = storage.get_inner().wait().unwrap();
+    for x in store::iterate_range(&*guard, &from, &tip.hash())? {
         let info = x?;
-        let (blk, _) = storage.get_block(&info.block_hash)?;
+        let (blk, _) = guard.get_block(&info.block_hash)?;
+        check_served(storage, &blk)?;
         if let Err(_) = reply.send(blk) {
             break;
         }
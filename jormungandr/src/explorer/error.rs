@@ -1,8 +1,13 @@
 use chain_storage::error::Error as StorageError;
 
 error_chain! {
+    links {
+        Blockchain(crate::blockchain::Error, crate::blockchain::ErrorKind);
+    }
     foreign_links {
         StorageError(StorageError);
+        IoError(std::io::Error);
+        PersistenceError(sled::Error);
     }
     errors {
         BlockNotFound(hash: String) {
@@ -25,6 +30,10 @@ error_chain! {
             description("tried to index already indexed chainlength in the given branch")
             display("chain length: {} is already indexed", chain_length)
         }
+        CorruptedRecord(key: String) {
+            description("failed to read a record of the explorer's database")
+            display("the explorer's database record '{}' is corrupted", key)
+        }
         BootstrapError(msg: String) {
             description("failed to initialize explorer's database from storage")
             display("the explorer's database couldn't be initialized: {}", msg)
@@ -4,7 +4,7 @@ mod scalars;
 use self::connections::{BlockConnection, BlockCursor};
 use self::error::ErrorKind;
 use super::indexing::{
-    BlockProducer, EpochData, ExplorerBlock, ExplorerTransaction, PersistentSequence,
+    BlockProducer, EpochData, ExplorerBlock, ExplorerTransaction, ExplorerUtxo, PersistentSequence,
 };
 use crate::blockcfg::{self, FragmentId, HeaderHash};
 use chain_impl_mockchain::certificate;
@@ -316,8 +316,44 @@ impl Address {
             .to_string()
     }
 
-    fn delegation() -> FieldResult<Pool> {
-        Err(ErrorKind::Unimplemented.into())
+    /// The current balance of the address
+    fn balance(&self, context: &Context) -> FieldResult<Value> {
+        let balance = context
+            .db
+            .get_address_data(&self.id)
+            .wait()?
+            .map(|data| data.balance)
+            .unwrap_or(blockcfg::Value::zero());
+        Ok(Value::from(&balance))
+    }
+
+    /// The unspent outputs sent to the address, account addresses have none
+    fn utxos(&self, context: &Context) -> FieldResult<Vec<Utxo>> {
+        Ok(context
+            .db
+            .get_address_data(&self.id)
+            .wait()?
+            .map(|data| data.utxos.iter().cloned().map(Utxo::from).collect())
+            .unwrap_or(Vec::new()))
+    }
+
+    /// The stake pool the account of the address delegates to, if any
+    fn delegation(&self, context: &Context) -> FieldResult<Option<Pool>> {
+        let discrimination = context.db.blockchain_config.discrimination;
+        let account = match self.id.kind() {
+            chain_addr::Kind::Account(_) => self.id.clone(),
+            chain_addr::Kind::Group(_, account) => {
+                chain_addr::Address(discrimination, chain_addr::Kind::Account(account.clone()))
+            }
+            chain_addr::Kind::Single(_) | chain_addr::Kind::Multisig(_) => return Ok(None),
+        };
+
+        Ok(context
+            .db
+            .get_address_data(&account)
+            .wait()?
+            .and_then(|data| data.delegation)
+            .map(Pool::from_valid_id))
     }
 
     fn transactions(&self, context: &Context) -> FieldResult<Vec<Transaction>> {
@@ -335,6 +371,35 @@ impl Address {
     }
 }
 
+struct Utxo {
+    utxo: ExplorerUtxo,
+}
+
+impl From<ExplorerUtxo> for Utxo {
+    fn from(utxo: ExplorerUtxo) -> Utxo {
+        Utxo { utxo }
+    }
+}
+
+/// A transaction output that is not spent yet
+#[juniper::object(
+    Context = Context
+)]
+impl Utxo {
+    fn transaction(&self, context: &Context) -> FieldResult<Transaction> {
+        Transaction::from_id(self.utxo.transaction_id, context)
+    }
+
+    /// The index of the output in the transaction
+    fn output_index(&self) -> i32 {
+        self.utxo.output_index.into()
+    }
+
+    fn amount(&self) -> Value {
+        Value::from(&self.utxo.value)
+    }
+}
+
 /*--------------------------------------------*/
 /*------------------Certificates-------------*/
 /*------------------------------------------*/
@@ -559,9 +624,15 @@ struct Status {}
     Context = Context
 )]
 impl Status {
-    pub fn current_epoch(&self) -> FieldResult<Epoch> {
-        // TODO: Would this be the Epoch of last block or a timeframe reference?
-        Err(ErrorKind::Unimplemented.into())
+    /// The epoch of the current time, given by the slot duration and the
+    /// number of slots per epoch of the block0
+    pub fn current_epoch(&self, context: &Context) -> FieldResult<Epoch> {
+        context
+            .db
+            .blockchain_config
+            .current_epoch()
+            .map(|id| Epoch { id })
+            .ok_or(ErrorKind::InternalError("the block0 is in the future".to_owned()).into())
     }
 
     pub fn latest_block(&self, context: &Context) -> FieldResult<Block> {
@@ -574,9 +645,14 @@ impl Status {
             .map(|b| Block::from(&b))
     }
 
-    pub fn fee_settings(&self) -> FieldResult<FeeSettings> {
-        // TODO: Where can I get this?
-        Err(ErrorKind::Unimplemented.into())
+    /// The fee settings in application at the latest block
+    pub fn fee_settings(&self, context: &Context) -> FieldResult<FeeSettings> {
+        let fees = context.db.get_fee_settings().wait()?;
+        Ok(FeeSettings {
+            constant: Value::from(&blockcfg::Value(fees.constant)),
+            coefficient: Value::from(&blockcfg::Value(fees.coefficient)),
+            certificate: Value::from(&blockcfg::Value(fees.certificate)),
+        })
     }
 }
 
@@ -611,9 +687,20 @@ impl Epoch {
         self.id.into()
     }
 
-    /// Not yet implemented
-    pub fn stake_distribution(&self) -> FieldResult<StakeDistribution> {
-        Err(ErrorKind::Unimplemented.into())
+    /// The stake distribution the leaders of the epoch are elected from,
+    /// null if the epoch is unknown or under BFT
+    pub fn stake_distribution(&self, context: &Context) -> Option<StakeDistribution> {
+        self.get_epoch_data(&context.db)
+            .and_then(|data| data.stake_distribution)
+            .map(|distribution| StakeDistribution {
+                pools: distribution
+                    .iter()
+                    .map(|(pool_id, stake)| PoolStakeDistribution {
+                        pool: Pool::from_valid_id(pool_id.clone()),
+                        delegated_stake: Value::from(stake),
+                    })
+                    .collect(),
+            })
     }
 
     /// Get a paginated view of all the blocks in this epoch
@@ -774,6 +861,68 @@ impl Query {
     }
 }
 
+/// The root of the subscriptions, resolved each time a block becomes the tip
+/// of the explorer's longest chain
+#[derive(Clone)]
+pub struct Subscription {
+    block: HeaderHash,
+    epoch_transition: Option<blockcfg::Epoch>,
+}
+
+impl Subscription {
+    /// `epoch_transition` is the epoch started by the block, if it is the
+    /// first block of its epoch
+    pub fn new(block: HeaderHash, epoch_transition: Option<blockcfg::Epoch>) -> Subscription {
+        Subscription {
+            block,
+            epoch_transition,
+        }
+    }
+}
+
+#[juniper::object(
+    Context = Context,
+)]
+impl Subscription {
+    /// The new tip
+    pub fn new_block(&self) -> Block {
+        Block::from_valid_hash(self.block)
+    }
+
+    /// The transactions of the new tip with the address in their inputs
+    /// or outputs
+    pub fn new_transactions(
+        &self,
+        address: String,
+        context: &Context,
+    ) -> FieldResult<Vec<Transaction>> {
+        let address = Address::from_bech32(&address)?;
+        let block = Block::from_valid_hash(self.block).get_explorer_block(&context.db)?;
+
+        Ok(block
+            .transactions
+            .values()
+            .filter(|tx| {
+                tx.inputs().iter().any(|input| input.address == address.id)
+                    || tx
+                        .outputs()
+                        .iter()
+                        .any(|output| output.address == address.id)
+            })
+            .map(|tx| Transaction {
+                id: tx.id(),
+                in_block: self.block,
+            })
+            .collect())
+    }
+
+    /// The epoch started by the new tip, null if the tip is not the first
+    /// block of its epoch
+    pub fn epoch_transition(&self) -> Option<Epoch> {
+        self.epoch_transition.map(|id| Epoch { id })
+    }
+}
+
 pub struct Context {
     pub db: ExplorerDB,
     pub settings: Settings,
@@ -786,3 +935,11 @@ pub type Schema = RootNode<'static, Query, EmptyMutation<Context>>;
 pub fn create_schema() -> Schema {
     Schema::new(Query {}, EmptyMutation::new())
 }
+
+/// The schema the subscriptions are executed with for a new tip, its root
+/// is the `Subscription` of the tip
+pub type SubscriptionSchema = RootNode<'static, Subscription, EmptyMutation<Context>>;
+
+pub fn create_subscription_schema(subscription: Subscription) -> SubscriptionSchema {
+    SubscriptionSchema::new(subscription, EmptyMutation::new())
+}
@@ -2,13 +2,15 @@ use super::set::HamtSet as Set;
 use imhamt;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::blockcfg::{Block, BlockDate, ChainLength, Epoch, Fragment, FragmentId, HeaderHash};
-use chain_addr::{Address, Discrimination};
+use chain_addr::{Address, Discrimination, Kind};
 use chain_core::property::Block as _;
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::block::Proof;
 use chain_impl_mockchain::certificate::{Certificate, PoolId};
+use chain_impl_mockchain::fee::LinearFee;
 use chain_impl_mockchain::leadership::bft;
 use chain_impl_mockchain::transaction::{AuthenticatedTransaction, InputEnum, Witness};
 use chain_impl_mockchain::value::Value;
@@ -19,7 +21,7 @@ pub type Transactions = Hamt<FragmentId, HeaderHash>;
 pub type Blocks = Hamt<HeaderHash, ExplorerBlock>;
 pub type ChainLengths = Hamt<ChainLength, HeaderHash>;
 
-pub type Addresses = Hamt<Address, Set<FragmentId>>;
+pub type Addresses = Hamt<Address, AddressData>;
 pub type Epochs = Hamt<Epoch, EpochData>;
 
 // Use a Hamt to store a sequence, the indexes can be used for pagination
@@ -64,6 +66,8 @@ pub struct ExplorerTransaction {
 pub struct ExplorerInput {
     pub address: Address,
     pub value: Value,
+    /// The output spent by the input, None for account inputs
+    pub utxo: Option<ExplorerUtxo>,
 }
 
 #[derive(Clone)]
@@ -72,11 +76,50 @@ pub struct ExplorerOutput {
     pub value: Value,
 }
 
+/// Transaction output that can be spent as an utxo input
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct ExplorerUtxo {
+    pub transaction_id: FragmentId,
+    pub output_index: u8,
+    pub value: Value,
+}
+
+/// What is known of an address at a given block
+#[derive(Clone)]
+pub struct AddressData {
+    /// The transactions with the address in their inputs or outputs
+    pub transactions: Set<FragmentId>,
+    pub balance: Value,
+    /// The unspent outputs sent to the address, always empty for account
+    /// addresses
+    pub utxos: Set<ExplorerUtxo>,
+    /// The stake pool the address delegates to, only set for account
+    /// addresses
+    pub delegation: Option<PoolId>,
+}
+
 #[derive(Clone)]
 pub struct EpochData {
     pub first_block: HeaderHash,
     pub last_block: HeaderHash,
     pub total_blocks: u32,
+    /// The stake delegated to each pool for the leader election in the
+    /// epoch, captured when its first block is applied. None for BFT epochs
+    pub stake_distribution: Option<Arc<PoolStakes>>,
+}
+
+pub type PoolStakes = HashMap<PoolId, Value>;
+
+/// Everything a block adds to the indexes: the block itself and the data
+/// taken from the ledger when it was applied. The indexes can be rebuilt
+/// from the records alone
+#[derive(Clone)]
+pub struct BlockRecord {
+    pub block: ExplorerBlock,
+    /// The fee settings of the block's epoch
+    pub fees: LinearFee,
+    /// Only set for the first block of a Genesis Praos epoch
+    pub stake_distribution: Option<Arc<PoolStakes>>,
 }
 
 impl ExplorerBlock {
@@ -241,7 +284,11 @@ impl ExplorerTransaction {
                             .into(),
                     );
                     let address = Address(discrimination, kind);
-                    Some(ExplorerInput { address, value })
+                    Some(ExplorerInput {
+                        address,
+                        value,
+                        utxo: None,
+                    })
                 }
                 (InputEnum::AccountInput(_id, _value), Witness::Multisig(_)) => {
                     // TODO
@@ -260,6 +307,11 @@ impl ExplorerTransaction {
                     Some(ExplorerInput {
                         address: output.address.clone(),
                         value: output.value,
+                        utxo: Some(ExplorerUtxo {
+                            transaction_id: tx,
+                            output_index: index,
+                            value: output.value,
+                        }),
                     })
                 }
                 _ => None,
@@ -287,6 +339,41 @@ impl ExplorerTransaction {
     }
 }
 
+impl ExplorerUtxo {
+    /// Get the outputs of the transaction that can be spent as utxo inputs,
+    /// the outputs sent to account addresses are credited to the account
+    /// instead
+    pub fn from_outputs(tx: &ExplorerTransaction) -> Vec<(Address, ExplorerUtxo)> {
+        tx.outputs()
+            .iter()
+            .enumerate()
+            .filter(|(_index, output)| match output.address.kind() {
+                Kind::Account(_) | Kind::Multisig(_) => false,
+                Kind::Single(_) | Kind::Group(_, _) => true,
+            })
+            .map(|(index, output)| {
+                let utxo = ExplorerUtxo {
+                    transaction_id: tx.id(),
+                    output_index: index as u8,
+                    value: output.value,
+                };
+                (output.address.clone(), utxo)
+            })
+            .collect()
+    }
+}
+
+impl AddressData {
+    pub fn new() -> Self {
+        AddressData {
+            transactions: Set::new(),
+            balance: Value::zero(),
+            utxos: Set::new(),
+            delegation: None,
+        }
+    }
+}
+
 impl<T> PersistentSequence<T> {
     pub fn new() -> Self {
         PersistentSequence {
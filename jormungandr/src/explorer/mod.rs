@@ -1,30 +1,37 @@
 pub mod error;
 pub mod graphql;
 mod indexing;
+mod persistence;
 mod set;
 
 use self::error::{Error, ErrorKind, Result};
 use self::graphql::Context;
 use self::indexing::{
-    Addresses, Blocks, ChainLengths, EpochData, Epochs, ExplorerBlock, PersistentSequence,
-    StakePools, Transactions,
+    AddressData, Addresses, BlockRecord, Blocks, ChainLengths, EpochData, Epochs, ExplorerBlock,
+    ExplorerUtxo, PersistentSequence, PoolStakes, StakePools, Transactions,
 };
+pub use self::persistence::ExplorerStore;
 use self::set::HamtSet as Set;
 
-use self::future::Either;
 use crate::blockcfg::{
     Block, ChainLength, ConfigParam, ConfigParams, ConsensusVersion, Epoch, Fragment, FragmentId,
     HeaderHash,
 };
-use crate::blockchain::{Blockchain, Multiverse, MAIN_BRANCH_TAG};
+use crate::blockchain::{Blockchain, Multiverse, Ref, MAIN_BRANCH_TAG};
 use crate::intercom::ExplorerMsg;
+use crate::utils::broadcast::Broadcaster;
 use crate::utils::task::{Input, TokioServiceInfo};
 use chain_addr::{Address, Discrimination};
 use chain_core::property::Block as _;
 use chain_impl_mockchain::certificate::{Certificate, PoolId};
+use chain_impl_mockchain::fee::LinearFee;
+use chain_impl_mockchain::leadership::LeadershipConsensus;
 use chain_impl_mockchain::multiverse::GCRoot;
+use chain_time::era::TimeEra;
+use chain_time::{Slot, SlotDuration, TimeFrame, Timeline};
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::prelude::*;
 use tokio::sync::lock::{Lock, LockGuard};
 
@@ -32,8 +39,14 @@ use tokio::sync::lock::{Lock, LockGuard};
 pub struct Explorer {
     pub db: ExplorerDB,
     pub schema: Arc<graphql::Schema>,
+    pub subscribers: Subscribers,
 }
 
+/// The open subscription sessions, notified of each block the tip of the
+/// longest chain moves through. A session not keeping up with the blocks
+/// is dropped
+pub type Subscribers = Broadcaster<graphql::Subscription>;
+
 struct Branch {
     id: HeaderHash,
     length: ChainLength,
@@ -53,6 +66,13 @@ pub struct ExplorerDB {
     /// multiverse, and the ChainLength is used in the updating process.
     longest_chain_tip: Tip,
     pub blockchain_config: BlockchainConfig,
+    /// Used to get the ledger related data of the blocks, like the stake
+    /// distribution of the epochs
+    blockchain: Blockchain,
+    /// On disk copy of the indexes, None if the node keeps its blocks in
+    /// memory
+    store: Option<ExplorerStore>,
+    subscribers: Subscribers,
 }
 
 #[derive(Clone)]
@@ -61,6 +81,10 @@ pub struct BlockchainConfig {
     /// inputs
     discrimination: Discrimination,
     consensus_version: ConsensusVersion,
+    /// Used to get the current epoch. The era is the one of the block0, the
+    /// number of slots per epoch is assumed not to be changed by an update
+    time_frame: TimeFrame,
+    era: TimeEra,
 }
 
 /// Inmutable data structure used to represent the explorer's state at a given Block
@@ -74,6 +98,8 @@ struct State {
     epochs: Epochs,
     chain_lengths: ChainLengths,
     stake_pools: StakePools,
+    /// The fee settings in application at the block
+    fees: LinearFee,
 }
 
 #[derive(Clone)]
@@ -88,6 +114,7 @@ pub struct Settings {
 impl Explorer {
     pub fn new(db: ExplorerDB, schema: graphql::Schema) -> Explorer {
         Explorer {
+            subscribers: db.subscribers.clone(),
             db,
             schema: Arc::new(schema),
         }
@@ -132,96 +159,182 @@ impl Explorer {
 }
 
 impl ExplorerDB {
-    /// Apply all the blocks in the [block0, MAIN_BRANCH_TAG], also extract the static
+    /// Load the indexes of the blocks already in the explorer's store, then
+    /// apply the blocks up to the MAIN_BRANCH_TAG, also extract the static
     /// Blockchain settings from the Block0 (Discrimination)
+    /// The indexes are restored from the store's checkpoint, and only the
+    /// records after it are applied again. If the node switched to another
+    /// branch since the store was written, the indexes are rolled back to the
+    /// fork point first.
     /// This function is only called once on the node's bootstrap phase
-    pub fn bootstrap(block0: Block, blockchain: &Blockchain) -> Result<Self> {
-        let blockchain_config = BlockchainConfig::from_config_params(
-            block0
-                .contents
-                .iter()
-                .filter_map(|fragment| match fragment {
-                    Fragment::Initial(config_params) => Some(config_params),
-                    _ => None,
-                })
-                .next()
-                .expect("the Initial fragment to be present in the genesis block"),
-        );
+    pub fn bootstrap(
+        block0: Block,
+        blockchain: &Blockchain,
+        store: Option<ExplorerStore>,
+    ) -> Result<Self> {
+        let config_params = block0
+            .contents
+            .iter()
+            .filter_map(|fragment| match fragment {
+                Fragment::Initial(config_params) => Some(config_params),
+                _ => None,
+            })
+            .next()
+            .expect("the Initial fragment to be present in the genesis block");
+        let blockchain_config = BlockchainConfig::from_config_params(config_params);
+        let discrimination = blockchain_config.discrimination;
+        let fees = config_params
+            .iter()
+            .filter_map(|param| match param {
+                ConfigParam::LinearFee(fees) => Some(fees.clone()),
+                _ => None,
+            })
+            .next()
+            .expect("the fees to be present");
 
-        let block = ExplorerBlock::resolve_from(
-            &block0,
-            blockchain_config.discrimination,
-            &Transactions::new(),
-            &Blocks::new(),
-        );
+        let head = blockchain
+            .storage()
+            .get_tag(MAIN_BRANCH_TAG.to_owned())
+            .wait()?
+            .ok_or(ErrorKind::BootstrapError(
+                "Couldn't read the HEAD tag from storage".to_owned(),
+            ))?;
 
-        let blocks = apply_block_to_blocks(Blocks::new(), &block)?;
-        let epochs = apply_block_to_epochs(Epochs::new(), &block);
-        let chain_lengths = apply_block_to_chain_lengths(ChainLengths::new(), &block)?;
-        let transactions = apply_block_to_transactions(Transactions::new(), &block)?;
-        let addresses = apply_block_to_addresses(Addresses::new(), &block)?;
-        let stake_pools = apply_block_to_stake_pools(StakePools::new(), &block);
-
-        let initial_state = State {
-            blocks,
-            epochs,
-            chain_lengths,
-            transactions,
-            addresses,
-            stake_pools,
+        let stored_tip = match &store {
+            Some(store) => store.get_tip()?,
+            None => None,
         };
 
         let multiverse = Multiverse::<State>::new();
-        multiverse
-            .insert(block0.chain_length(), block0.id(), initial_state)
-            .wait()
-            .expect("The multiverse to be empty");
 
-        let block0_id = block0.id().clone();
+        let (fork_point, fork_length) = match (&store, stored_tip) {
+            (Some(store), Some(stored_tip)) => {
+                if store.get_block_at(ChainLength::from(0))? != Some(block0.id()) {
+                    return Err(ErrorKind::BootstrapError(
+                        "the explorer's database belongs to another blockchain".to_owned(),
+                    )
+                    .into());
+                }
+
+                // the fork point is the stored tip itself unless the node
+                // switched to another branch, in which case the blocks after
+                // it are rolled back
+                let fork_point = blockchain
+                    .storage()
+                    .find_common_ancestor(stored_tip, head)
+                    .wait()?;
+                let fork_length = store
+                    .get_record(&fork_point)?
+                    .ok_or_else(|| ErrorKind::CorruptedRecord(fork_point.to_string()))?
+                    .block
+                    .chain_length();
+
+                // the indexes are restored from the checkpoint if it is an
+                // ancestor of the fork point, and the stored blocks after it
+                // are indexed from their records, without reading them from
+                // the storage nor resolving their inputs again
+                let (block_id, mut state) = match restore_checkpoint(store, fork_length)? {
+                    Some(checkpoint) => checkpoint,
+                    None => {
+                        let block0_record = store
+                            .get_record(&block0.id())?
+                            .ok_or_else(|| ErrorKind::CorruptedRecord(block0.id().to_string()))?;
+                        let state =
+                            State::new(fees).apply_record(&block0_record, discrimination)?;
+                        (block0.id(), state)
+                    }
+                };
+                let restored_length = state
+                    .blocks
+                    .lookup(&block_id)
+                    .expect("the restored block to be indexed")
+                    .chain_length();
+                multiverse
+                    .insert(restored_length, block_id, state.clone())
+                    .wait()
+                    .unwrap_or_else(|err: Infallible| match err {});
+
+                if restored_length < fork_length {
+                    for record in store.main_branch(restored_length.increase(), fork_length) {
+                        let record = record?;
+                        state = state.apply_record(&record, discrimination)?;
+                        multiverse
+                            .insert(
+                                record.block.chain_length(),
+                                record.block.id(),
+                                state.clone(),
+                            )
+                            .wait()
+                            .unwrap_or_else(|err: Infallible| match err {});
+                    }
+                }
+
+                (fork_point, fork_length)
+            }
+            _ => {
+                let block0_ref = blockchain.clone().get_ref(block0.id()).wait()?;
+                let record = block_record(&block0, &State::new(fees), block0_ref, discrimination);
+                if let Some(store) = &store {
+                    store.put_record(&record)?;
+                    store.set_tip(&record.block.id())?;
+                }
+                let state = State::new(fees).apply_record(&record, discrimination)?;
+                multiverse
+                    .insert(block0.chain_length(), block0.id(), state)
+                    .wait()
+                    .expect("The multiverse to be empty");
+                (block0.id(), block0.chain_length())
+            }
+        };
 
         let bootstraped_db = ExplorerDB {
             multiverse,
             longest_chain_tip: Tip::new(Branch {
-                id: block0.id(),
-                length: block0.header.chain_length(),
+                id: fork_point,
+                length: fork_length,
             }),
             blockchain_config,
+            blockchain: blockchain.clone(),
+            store,
+            subscribers: Subscribers::new(),
         };
 
-        blockchain
+        let stream = blockchain
             .storage()
-            .get_tag(MAIN_BRANCH_TAG.to_owned())
-            .map_err(|err| err.into())
-            .and_then(move |head_option| match head_option {
-                None => Either::A(future::err(Error::from(ErrorKind::BootstrapError(
-                    "Couldn't read the HEAD tag from storage".to_owned(),
-                )))),
-                Some(head) => Either::B(
-                    blockchain
-                        .storage()
-                        .stream_from_to(block0_id, head)
-                        .map_err(|err| Error::from(err)),
-                ),
-            })
-            .and_then(move |stream_option| match stream_option {
-                None => Either::A(future::err(Error::from(ErrorKind::BootstrapError(
-                    "Couldn't iterate from Block0 to HEAD".to_owned(),
-                )))),
-                Some(stream) => Either::B(future::ok(stream)),
-            })
-            .and_then(move |stream| {
-                stream
-                    .map_err(|err| Error::from(err))
-                    .fold(bootstraped_db, |mut db, block| {
-                        db.apply_block(block).and_then(|_gc_root| Ok(db))
-                    })
+            .stream_from_to(fork_point, head)
+            .wait()?
+            .ok_or(ErrorKind::BootstrapError(
+                "Couldn't iterate from the explorer's tip to HEAD".to_owned(),
+            ))?;
+
+        let db = stream
+            .map_err(|err| Error::from(err))
+            .fold(bootstraped_db, |mut db, block| {
+                db.apply_block(block).and_then(|_gc_root| Ok(db))
             })
-            .wait()
+            .wait()?;
+
+        if let Some(store) = &db.store {
+            let tip = db
+                .get_latest_block_hash()
+                .wait()
+                .unwrap_or_else(|err: Infallible| match err {});
+            store.set_tip(&tip)?;
+            // the indexes are rebuilt from the stored tip on restart,
+            // make sure it is on disk once the rebuild is done
+            store.flush()?;
+        }
+
+        Ok(db)
     }
 
     /// Try to add a new block to the indexes, this can fail if the parent of the block is
     /// not processed. Also, update the longest seen chain with this block as tip if its
     /// chain length is greater than the current.
+    /// The record of the block is written to the explorer's store, if any, and so is
+    /// the new tip of the longest chain, along with a new checkpoint of the indexes
+    /// when the tip enters another epoch. The subscribers are notified of each block
+    /// the tip moves through.
     /// This doesn't perform any validation on the given block and the previous state, it
     /// is assumed that the Block is valid
     pub fn apply_block(&mut self, block: Block) -> impl Future<Item = GCRoot, Error = Error> {
@@ -231,67 +344,61 @@ impl ExplorerDB {
         let multiverse = self.multiverse.clone();
         let current_tip = self.longest_chain_tip.clone();
         let discrimination = self.blockchain_config.discrimination.clone();
+        let get_block_ref = self.blockchain.get_ref(block_id).map_err(Error::from);
+        let store = self.store.clone();
+        let tip_store = self.store.clone();
+        let tip_multiverse = self.multiverse.clone();
+        let subscribers = self.subscribers.clone();
 
         multiverse
             .get(previous_block)
             .map_err(|_: Infallible| unreachable!())
-            .and_then(move |maybe_previous_state| match maybe_previous_state {
-                Some(state) => {
-                    let State {
-                        transactions,
-                        blocks,
-                        addresses,
-                        epochs,
-                        chain_lengths,
-                        stake_pools,
-                    } = state;
-
-                    let explorer_block =
-                        ExplorerBlock::resolve_from(&block, discrimination, &transactions, &blocks);
-
-                    Ok((
-                        apply_block_to_transactions(transactions, &explorer_block)?,
-                        apply_block_to_blocks(blocks, &explorer_block)?,
-                        apply_block_to_addresses(addresses, &explorer_block)?,
-                        apply_block_to_epochs(epochs, &explorer_block),
-                        apply_block_to_chain_lengths(chain_lengths, &explorer_block)?,
-                        apply_block_to_stake_pools(stake_pools, &explorer_block),
-                    ))
-                }
-                None => Err(Error::from(ErrorKind::AncestorNotFound(format!(
-                    "{}",
-                    block.id()
-                )))),
-            })
+            .join(get_block_ref)
             .and_then(
-                move |(transactions, blocks, addresses, epochs, chain_lengths, stake_pools)| {
-                    let chain_length = chain_length.clone();
-                    let block_id = block_id.clone();
-                    multiverse
-                        .insert(
-                            chain_length,
-                            block_id,
-                            State {
-                                transactions,
-                                blocks,
-                                addresses,
-                                epochs,
-                                chain_lengths,
-                                stake_pools,
-                            },
-                        )
-                        .map_err(|_: Infallible| unreachable!())
-                        .map(move |gc_root| (gc_root, block_id, chain_length))
+                move |(maybe_previous_state, block_ref)| match maybe_previous_state {
+                    Some(state) => {
+                        let record = block_record(&block, &state, block_ref, discrimination);
+                        let state = state.apply_record(&record, discrimination)?;
+                        if let Some(store) = &store {
+                            store.put_record(&record)?;
+                        }
+                        Ok(state)
+                    }
+                    None => Err(Error::from(ErrorKind::AncestorNotFound(format!(
+                        "{}",
+                        block.id()
+                    )))),
                 },
             )
-            .and_then(move |(gc_root, block_id, chain_length)| {
+            .and_then(move |state| {
+                let chain_length = chain_length.clone();
+                let block_id = block_id.clone();
+                multiverse
+                    .insert(chain_length, block_id, state.clone())
+                    .map_err(|_: Infallible| unreachable!())
+                    .map(move |gc_root| (gc_root, block_id, chain_length, state))
+            })
+            .and_then(move |(gc_root, block_id, chain_length, state)| {
                 current_tip
                     .compare_and_replace(Branch {
                         id: block_id,
                         length: chain_length,
                     })
                     .map_err(|_: Infallible| unreachable!())
-                    .map(|_| gc_root)
+                    .and_then(move |replaced| match replaced {
+                        Some(previous_tip) => future::Either::A(
+                            replace_tip(
+                                tip_store,
+                                tip_multiverse,
+                                subscribers,
+                                previous_tip,
+                                block_id,
+                                state,
+                            )
+                            .map(move |()| gc_root),
+                        ),
+                        None => future::Either::B(future::ok(gc_root)),
+                    })
             })
     }
 
@@ -299,6 +406,12 @@ impl ExplorerDB {
         self.longest_chain_tip.blockid()
     }
 
+    pub fn get_latest_block_chain_length(
+        &self,
+    ) -> impl Future<Item = ChainLength, Error = Infallible> {
+        self.longest_chain_tip.chain_length()
+    }
+
     pub fn get_block(
         &self,
         block_id: &HeaderHash,
@@ -345,7 +458,20 @@ impl ExplorerDB {
         address: &Address,
     ) -> impl Future<Item = Option<Set<FragmentId>>, Error = Infallible> {
         let address = address.clone();
-        self.with_latest_state(move |state| state.addresses.lookup(&address).map(|set| set.clone()))
+        self.with_latest_state(move |state| {
+            state
+                .addresses
+                .lookup(&address)
+                .map(|data| data.transactions.clone())
+        })
+    }
+
+    pub fn get_address_data(
+        &self,
+        address: &Address,
+    ) -> impl Future<Item = Option<AddressData>, Error = Infallible> {
+        let address = address.clone();
+        self.with_latest_state(move |state| state.addresses.lookup(&address).map(|d| d.clone()))
     }
 
     // Get the hashes of all blocks in the range [from, to)
@@ -371,6 +497,10 @@ impl ExplorerDB {
         })
     }
 
+    pub fn get_fee_settings(&self) -> impl Future<Item = LinearFee, Error = Infallible> {
+        self.with_latest_state(move |state| state.fees)
+    }
+
     pub fn get_stake_pool_blocks(
         &self,
         pool: &PoolId,
@@ -394,6 +524,201 @@ impl ExplorerDB {
     }
 }
 
+impl State {
+    /// The state before the block0
+    fn new(fees: LinearFee) -> Self {
+        State {
+            transactions: Transactions::new(),
+            blocks: Blocks::new(),
+            addresses: Addresses::new(),
+            epochs: Epochs::new(),
+            chain_lengths: ChainLengths::new(),
+            stake_pools: StakePools::new(),
+            fees,
+        }
+    }
+
+    /// Get the state of the block from the state of its parent and its record
+    fn apply_record(self, record: &BlockRecord, discrimination: Discrimination) -> Result<State> {
+        let block = &record.block;
+        Ok(State {
+            transactions: apply_block_to_transactions(self.transactions, block)?,
+            blocks: apply_block_to_blocks(self.blocks, block)?,
+            addresses: apply_block_to_addresses(self.addresses, block, discrimination)?,
+            epochs: apply_block_to_epochs(self.epochs, block, record.stake_distribution.clone()),
+            chain_lengths: apply_block_to_chain_lengths(self.chain_lengths, block)?,
+            stake_pools: apply_block_to_stake_pools(self.stake_pools, block),
+            fees: record.fees,
+        })
+    }
+}
+
+/// Once the block replaced the tip of the longest chain, write the new tip
+/// to the store and notify the subscribers of the blocks the tip moved
+/// through: only the block itself when it extends the previous tip, all the
+/// blocks of its branch after the fork point when the tip switched to
+/// another branch
+fn replace_tip(
+    store: Option<ExplorerStore>,
+    multiverse: Multiverse<State>,
+    subscribers: Subscribers,
+    previous_tip: HeaderHash,
+    block_id: HeaderHash,
+    state: State,
+) -> impl Future<Item = (), Error = Error> {
+    let checkpoint_multiverse = multiverse.clone();
+    multiverse
+        .get(previous_tip)
+        .map_err(|_: Infallible| unreachable!())
+        .and_then(move |previous_state| {
+            let previous_state = previous_state.expect("the previous tip to be indexed");
+            if let Some(store) = &store {
+                if let Err(err) = store.set_tip(&block_id) {
+                    return future::Either::A(future::err(err));
+                }
+            }
+
+            for block in new_branch(&previous_state, &state, block_id) {
+                let epoch = block.date().epoch;
+                let epoch_transition = state
+                    .epochs
+                    .lookup(&epoch)
+                    .filter(|data| data.first_block == block.id())
+                    .map(|_| epoch);
+                subscribers.send(graphql::Subscription::new(block.id(), epoch_transition));
+            }
+
+            match store {
+                Some(store) => future::Either::B(write_checkpoint(
+                    store,
+                    checkpoint_multiverse,
+                    &state,
+                    block_id,
+                )),
+                None => future::Either::A(future::ok(())),
+            }
+        })
+}
+
+/// The blocks from the fork point of the previous tip and the new one, to
+/// the new tip, in chain order
+fn new_branch<'a>(
+    previous_state: &State,
+    state: &'a State,
+    tip: HeaderHash,
+) -> Vec<&'a ExplorerBlock> {
+    let mut blocks = Vec::new();
+    let mut block = state.blocks.lookup(&tip);
+    while let Some(current) = block {
+        let chain_length = current.chain_length();
+        if previous_state.chain_lengths.lookup(&chain_length) == Some(&current.id()) {
+            break;
+        }
+        blocks.push(current);
+        block = state.blocks.lookup(&current.parent_hash);
+    }
+    blocks.reverse();
+    blocks
+}
+
+/// The checkpoint of the store and its indexes, if it is on the longest
+/// chain at or before the given chain length
+fn restore_checkpoint(
+    store: &ExplorerStore,
+    max_length: ChainLength,
+) -> Result<Option<(HeaderHash, State)>> {
+    let (block_id, state) = match store.get_checkpoint()? {
+        None => return Ok(None),
+        Some(checkpoint) => checkpoint,
+    };
+    let chain_length = match state.blocks.lookup(&block_id) {
+        None => return Err(ErrorKind::CorruptedRecord("checkpoint".to_owned()).into()),
+        Some(block) => block.chain_length(),
+    };
+    if chain_length <= max_length && store.get_block_at(chain_length)? == Some(block_id) {
+        Ok(Some((block_id, state)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// When the block is the first of its epoch, replace the store's checkpoint
+/// with the indexes at the first block of the previous epoch. Staying an
+/// epoch behind the tip, the checkpoint is still an ancestor of the fork
+/// point when the node switches to another branch, unless the rollback is
+/// longer than an epoch
+fn write_checkpoint(
+    store: ExplorerStore,
+    multiverse: Multiverse<State>,
+    state: &State,
+    block_id: HeaderHash,
+) -> impl Future<Item = (), Error = Error> {
+    let epoch = state
+        .blocks
+        .lookup(&block_id)
+        .expect("the block to be indexed")
+        .date()
+        .epoch;
+    let checkpoint_id = match state.epochs.lookup(&epoch) {
+        Some(data) if data.first_block == block_id && epoch > 0 => state
+            .epochs
+            .lookup(&(epoch - 1))
+            .map(|previous_epoch| previous_epoch.first_block),
+        _ => None,
+    };
+
+    match checkpoint_id {
+        None => future::Either::A(future::ok(())),
+        Some(checkpoint_id) => future::Either::B(
+            multiverse
+                .get(checkpoint_id)
+                .map_err(|_: Infallible| unreachable!())
+                .and_then(move |checkpoint| match checkpoint {
+                    // the indexes before the checkpoint restored on startup
+                    // are not in the multiverse
+                    None => Ok(()),
+                    Some(checkpoint) => store.put_checkpoint(&checkpoint_id, &checkpoint),
+                }),
+        ),
+    }
+}
+
+/// Resolve the block with the state of its parent and take the data the
+/// indexes need from its ledger `Ref`, if it is available
+fn block_record(
+    block: &Block,
+    previous_state: &State,
+    block_ref: Option<Arc<Ref>>,
+    discrimination: Discrimination,
+) -> BlockRecord {
+    let explorer_block = ExplorerBlock::resolve_from(
+        block,
+        discrimination,
+        &previous_state.transactions,
+        &previous_state.blocks,
+    );
+
+    // the stake distribution is only needed for the first block of the epoch
+    let stake_distribution = match previous_state.epochs.lookup(&explorer_block.date().epoch) {
+        Some(_) => None,
+        None => block_ref
+            .as_ref()
+            .and_then(|block_ref| epoch_stake_distribution(block_ref)),
+    };
+
+    // the ledger parameters of the epoch include the accepted updates of
+    // the settings
+    let fees = block_ref
+        .map(|block_ref| block_ref.epoch_ledger_parameters().fees)
+        .unwrap_or(previous_state.fees);
+
+    BlockRecord {
+        block: explorer_block,
+        fees,
+        stake_distribution,
+    }
+}
+
 fn get_lock<L>(lock: &Lock<L>) -> impl Future<Item = LockGuard<L>, Error = Infallible> {
     let mut lock = (*lock).clone();
     future::poll_fn(move || Ok(lock.poll_lock()))
@@ -423,39 +748,100 @@ fn apply_block_to_blocks(blocks: Blocks, block: &ExplorerBlock) -> Result<Blocks
         .map_err(|_| Error::from(ErrorKind::BlockAlreadyExists(format!("{}", block_id))))
 }
 
-fn apply_block_to_addresses(addresses: Addresses, block: &ExplorerBlock) -> Result<Addresses> {
+fn apply_block_to_addresses(
+    addresses: Addresses,
+    block: &ExplorerBlock,
+    discrimination: Discrimination,
+) -> Result<Addresses> {
     let mut addresses = addresses;
     let transactions = block.transactions.values();
 
-    for tx in transactions {
+    // the outputs of all the transactions are applied before their inputs,
+    // an input can spend an output of a transaction of the same block
+    for tx in transactions.clone() {
         let id = tx.id();
         for output in tx.outputs() {
-            addresses = addresses.insert_or_update_simple(
-                output.address.clone(),
-                Set::new().add_element(id.clone()),
-                |set| {
-                    let new_set = set.add_element(id.clone());
-                    Some(new_set)
-                },
-            )
+            addresses = update_address(addresses, &output.address, |data| AddressData {
+                transactions: data.transactions.add_element(id),
+                balance: (data.balance + output.value).expect("the output to be validated"),
+                ..data
+            })
         }
 
+        for (address, utxo) in ExplorerUtxo::from_outputs(tx) {
+            addresses = update_address(addresses, &address, |data| AddressData {
+                utxos: data.utxos.add_element(utxo.clone()),
+                ..data
+            })
+        }
+    }
+
+    for tx in transactions {
+        let id = tx.id();
         for input in tx.inputs() {
-            addresses = addresses.insert_or_update_simple(
-                input.address.clone(),
-                Set::new().add_element(id.clone()),
-                |set| {
-                    let new_set = set.add_element(id.clone());
-                    Some(new_set)
+            addresses = update_address(addresses, &input.address, |data| AddressData {
+                transactions: data.transactions.add_element(id),
+                balance: (data.balance - input.value).expect("the input to be validated"),
+                utxos: match &input.utxo {
+                    Some(utxo) => data.utxos.remove_element(utxo),
+                    None => data.utxos,
                 },
-            )
+                ..data
+            })
+        }
+
+        let delegation = match &tx.certificate {
+            Some(Certificate::StakeDelegation(delegation)) => {
+                let pool_id = delegation.pool_id.clone();
+                delegation.account_id.to_single_account().map(|single| {
+                    let kind = chain_addr::Kind::Account(single.into());
+                    (vec![Address(discrimination, kind)], pool_id)
+                })
+            }
+            Some(Certificate::OwnerStakeDelegation(delegation)) => {
+                // the owners delegating are the accounts of the inputs
+                let owners = tx
+                    .inputs()
+                    .iter()
+                    .filter(|input| input.utxo.is_none())
+                    .map(|input| input.address.clone())
+                    .collect();
+                Some((owners, delegation.pool_id.clone()))
+            }
+            _ => None,
+        };
+
+        if let Some((accounts, pool_id)) = delegation {
+            for account in accounts {
+                addresses = update_address(addresses, &account, |data| AddressData {
+                    delegation: Some(pool_id.clone()),
+                    ..data
+                })
+            }
         }
     }
 
     Ok(addresses)
 }
 
-fn apply_block_to_epochs(epochs: Epochs, block: &ExplorerBlock) -> Epochs {
+/// Replace the data of the address with the result of `f`, applied to its
+/// current data or to empty data if the address is not indexed yet
+fn update_address<F>(addresses: Addresses, address: &Address, f: F) -> Addresses
+where
+    F: FnOnce(AddressData) -> AddressData,
+{
+    let data = f(addresses
+        .lookup(address)
+        .cloned()
+        .unwrap_or_else(AddressData::new));
+    addresses.insert_or_update_simple(address.clone(), data.clone(), move |_| Some(data.clone()))
+}
+
+fn apply_block_to_epochs(
+    epochs: Epochs,
+    block: &ExplorerBlock,
+    stake_distribution: Option<Arc<PoolStakes>>,
+) -> Epochs {
     let epoch_id = block.date().epoch;
     let block_id = block.id();
 
@@ -465,17 +851,34 @@ fn apply_block_to_epochs(epochs: Epochs, block: &ExplorerBlock) -> Epochs {
             first_block: block_id,
             last_block: block_id,
             total_blocks: 0,
+            stake_distribution,
         },
         |data| {
             Some(EpochData {
                 last_block: block_id,
                 total_blocks: data.total_blocks + 1,
-                ..*data
+                ..data.clone()
             })
         },
     )
 }
 
+/// The stake delegated to each pool the leaders of the block's epoch are
+/// elected from, if the epoch is under Genesis Praos
+fn epoch_stake_distribution(block_ref: &Ref) -> Option<Arc<PoolStakes>> {
+    match block_ref.epoch_leadership_schedule().consensus() {
+        LeadershipConsensus::GenesisPraos(genesis) => Some(Arc::new(
+            genesis
+                .distribution()
+                .to_pools
+                .iter()
+                .map(|(pool_id, pool)| (pool_id.clone(), pool.total.total_stake))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
 fn apply_block_to_chain_lengths(
     chain_lengths: ChainLengths,
     block: &ExplorerBlock,
@@ -522,29 +925,43 @@ fn apply_block_to_stake_pools(stake_pools: StakePools, block: &ExplorerBlock) ->
 
 impl BlockchainConfig {
     fn from_config_params(params: &ConfigParams) -> BlockchainConfig {
-        let discrimination = params
-            .iter()
-            .filter_map(|param| match param {
-                ConfigParam::Discrimination(discrimination) => Some(discrimination.clone()),
-                _ => None,
-            })
-            .next()
-            .expect("the discrimination to be present");
+        let mut discrimination = None;
+        let mut consensus_version = None;
+        let mut block0_date = None;
+        let mut slot_duration = None;
+        let mut slots_per_epoch = None;
 
-        let consensus_version = params
-            .iter()
-            .filter_map(|param| match param {
-                ConfigParam::ConsensusVersion(version) => Some(version.clone()),
-                _ => None,
-            })
-            .next()
-            .expect("consensus version to be present");
+        for param in params.iter() {
+            match param {
+                ConfigParam::Discrimination(param) => discrimination = Some(*param),
+                ConfigParam::ConsensusVersion(param) => consensus_version = Some(param.clone()),
+                ConfigParam::Block0Date(param) => block0_date = Some(param.0),
+                ConfigParam::SlotDuration(param) => slot_duration = Some(*param),
+                ConfigParam::SlotsPerEpoch(param) => slots_per_epoch = Some(*param),
+                _ => (),
+            }
+        }
+
+        let block0_date = block0_date.expect("the block0 date to be present");
+        let slot_duration = slot_duration.expect("the slot duration to be present");
+        let slots_per_epoch = slots_per_epoch.expect("the slots per epoch to be present");
 
         BlockchainConfig {
-            discrimination,
-            consensus_version,
+            discrimination: discrimination.expect("the discrimination to be present"),
+            consensus_version: consensus_version.expect("consensus version to be present"),
+            time_frame: TimeFrame::new(
+                Timeline::new(SystemTime::UNIX_EPOCH + Duration::from_secs(block0_date)),
+                SlotDuration::from_secs(u32::from(slot_duration)),
+            ),
+            era: TimeEra::new(Slot::from(0), chain_time::Epoch(0), slots_per_epoch),
         }
     }
+
+    /// The epoch of the current time, None if the block0 is in the future
+    pub fn current_epoch(&self) -> Option<Epoch> {
+        let slot = self.time_frame.slot_at(&SystemTime::now())?;
+        self.era.from_slot_to_era(slot).map(|date| date.epoch.0)
+    }
 }
 
 impl Tip {
@@ -552,20 +969,96 @@ impl Tip {
         Tip(Lock::new(branch))
     }
 
-    fn compare_and_replace(&self, other: Branch) -> impl Future<Item = (), Error = Infallible> {
+    /// Returns the previous tip if it was replaced
+    fn compare_and_replace(
+        &self,
+        other: Branch,
+    ) -> impl Future<Item = Option<HeaderHash>, Error = Infallible> {
         get_lock(&self.0).and_then(move |mut current| {
             // Probably a different thing is needed for the == case
             if other.length > (*current).length {
-                *current = Branch {
-                    id: other.id,
-                    length: other.length,
-                };
+                let previous = std::mem::replace(
+                    &mut *current,
+                    Branch {
+                        id: other.id,
+                        length: other.length,
+                    },
+                );
+                Ok(Some(previous.id))
+            } else {
+                Ok(None)
             }
-            Ok(())
         })
     }
 
     fn blockid(&self) -> impl Future<Item = HeaderHash, Error = Infallible> {
         get_lock(&self.0).map(|guard| (*guard).id)
     }
+
+    fn chain_length(&self) -> impl Future<Item = ChainLength, Error = Infallible> {
+        get_lock(&self.0).map(|guard| (*guard).length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::indexing::{BlockProducer, BlockRecord, ExplorerBlock};
+    use super::{new_branch, State};
+    use crate::blockcfg::{BlockDate, ChainLength, HeaderHash};
+    use chain_addr::Discrimination;
+    use chain_core::mempack::{ReadBuf, Readable as _};
+    use chain_impl_mockchain::fee::LinearFee;
+    use std::collections::HashMap;
+
+    fn hash(n: u8) -> HeaderHash {
+        HeaderHash::read(&mut ReadBuf::from(&[n; 32][..])).unwrap()
+    }
+
+    /// the state after the given chain of empty blocks, as (id, parent)
+    fn chain(blocks: &[(u8, u8)]) -> State {
+        blocks.iter().enumerate().fold(
+            State::new(LinearFee::new(0, 0, 0)),
+            |state, (chain_length, (id, parent))| {
+                let record = BlockRecord {
+                    block: ExplorerBlock {
+                        transactions: HashMap::new(),
+                        id: hash(*id),
+                        date: BlockDate {
+                            epoch: 0,
+                            slot_id: chain_length as u32,
+                        },
+                        chain_length: ChainLength::from(chain_length as u32),
+                        parent_hash: hash(*parent),
+                        producer: BlockProducer::None,
+                    },
+                    fees: LinearFee::new(0, 0, 0),
+                    stake_distribution: None,
+                };
+                state.apply_record(&record, Discrimination::Test).unwrap()
+            },
+        )
+    }
+
+    fn ids(blocks: Vec<&ExplorerBlock>) -> Vec<HeaderHash> {
+        blocks.iter().map(|block| block.id()).collect()
+    }
+
+    #[test]
+    fn tip_moves_through_the_blocks_after_the_fork_point() {
+        let previous = chain(&[(1, 0), (2, 1), (3, 2)]);
+
+        // the new tip extends the previous one
+        let extended = chain(&[(1, 0), (2, 1), (3, 2), (4, 3)]);
+        assert_eq!(
+            ids(new_branch(&previous, &extended, hash(4))),
+            vec![hash(4)]
+        );
+
+        // the new tip is on a branch forking after the block 1
+        let switched = chain(&[(1, 0), (5, 1), (6, 5), (7, 6)]);
+        assert_eq!(
+            ids(new_branch(&previous, &switched, hash(7))),
+            vec![hash(5), hash(6), hash(7)]
+        );
+    }
 }
@@ -0,0 +1,870 @@
+//! On disk copy of the explorer indexes, kept in the embedded key-value
+//! database sled.
+//!
+//! The record of every applied block is stored, keyed by the block hash.
+//! The blocks of the explorer's longest chain are also indexed by chain
+//! length, which allows the indexes to be rebuilt in order on restart.
+//! When the longest chain switches to another branch, the chain length
+//! index is rolled back to the common ancestor of the two branches and
+//! rewritten with the blocks of the new branch.
+//!
+//! A checkpoint of the indexes at one block of the longest chain is also
+//! kept, so that on restart only the records after it are applied again.
+
+use super::error::{ErrorKind, Result};
+use super::indexing::{
+    AddressData, Addresses, BlockProducer, BlockRecord, Blocks, ChainLengths, EpochData, Epochs,
+    ExplorerBlock, ExplorerInput, ExplorerOutput, ExplorerTransaction, ExplorerUtxo,
+    PersistentSequence, PoolStakes, StakePools, Transactions,
+};
+use super::set::HamtSet as Set;
+use super::State;
+use crate::blockcfg::{BlockDate, ChainLength, HeaderHash, Value};
+use chain_addr::Address;
+use chain_core::mempack::{ReadBuf, ReadError, Readable as _};
+use chain_core::property::Serialize as _;
+use chain_impl_mockchain::certificate::PoolId;
+use chain_impl_mockchain::fee::LinearFee;
+use chain_impl_mockchain::leadership::bft::LeaderId;
+use jormungandr_lib::interfaces::Certificate;
+use std::path::Path;
+use std::sync::Arc;
+
+const RECORD_PREFIX: u8 = b'r';
+const CHAIN_LENGTH_PREFIX: u8 = b'c';
+const TIP_KEY: &[u8] = b"tip";
+const CHECKPOINT_KEY: &[u8] = b"checkpoint";
+
+#[derive(Clone)]
+pub struct ExplorerStore {
+    db: sled::Db,
+}
+
+impl ExplorerStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let db = sled::Db::open(path)?;
+        Ok(ExplorerStore { db })
+    }
+
+    pub fn put_record(&self, record: &BlockRecord) -> Result<()> {
+        let key = record_key(&record.block.id())?;
+        self.db.insert(key, encode_record(record)?)?;
+        Ok(())
+    }
+
+    pub fn get_record(&self, block_id: &HeaderHash) -> Result<Option<BlockRecord>> {
+        match self.db.get(record_key(block_id)?)? {
+            None => Ok(None),
+            Some(bytes) => decode_record(bytes.as_ref())
+                .map(Some)
+                .map_err(|_| ErrorKind::CorruptedRecord(block_id.to_string()).into()),
+        }
+    }
+
+    /// The tip of the longest chain, None if nothing was stored yet
+    pub fn get_tip(&self) -> Result<Option<HeaderHash>> {
+        match self.db.get(TIP_KEY)? {
+            None => Ok(None),
+            Some(bytes) => read_hash(bytes.as_ref())
+                .map(Some)
+                .map_err(|_| ErrorKind::CorruptedRecord("tip".to_owned()).into()),
+        }
+    }
+
+    /// The block of the longest chain with the given chain length
+    pub fn get_block_at(&self, chain_length: ChainLength) -> Result<Option<HeaderHash>> {
+        match self.db.get(chain_length_key(chain_length))? {
+            None => Ok(None),
+            Some(bytes) => read_hash(bytes.as_ref()).map(Some).map_err(|_| {
+                ErrorKind::CorruptedRecord(format!("chain length {}", u32::from(chain_length)))
+                    .into()
+            }),
+        }
+    }
+
+    /// Make the block the tip of the longest chain. The chain length index
+    /// is rewritten from the block back to the first of its ancestors
+    /// already in the index, the entries past the block are removed.
+    ///
+    /// The records of the block and of its ancestors must be stored.
+    pub fn set_tip(&self, tip: &HeaderHash) -> Result<()> {
+        let tip_record = self.expect_record(tip)?;
+        let tip_length = tip_record.block.chain_length();
+
+        let mut next_length = u32::from(tip_length) + 1;
+        while self
+            .db
+            .remove(chain_length_key(ChainLength::from(next_length)))?
+            .is_some()
+        {
+            next_length += 1;
+        }
+
+        let mut block = tip_record.block;
+        loop {
+            let chain_length = block.chain_length();
+            if self.get_block_at(chain_length)? == Some(block.id()) {
+                break;
+            }
+            self.db
+                .insert(chain_length_key(chain_length), hash_bytes(&block.id())?)?;
+            if u32::from(chain_length) == 0 {
+                break;
+            }
+            block = self.expect_record(&block.parent_hash)?.block;
+        }
+
+        self.db.insert(TIP_KEY, hash_bytes(tip)?)?;
+        Ok(())
+    }
+
+    /// Write the pending changes to disk. Sled also flushes them in the
+    /// background, this is for the points where they must be on disk,
+    /// without paying for a flush on every block.
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// The records of the longest chain with a chain length from `from` to
+    /// `to` included, read one at a time
+    pub fn main_branch(
+        &self,
+        from: ChainLength,
+        to: ChainLength,
+    ) -> impl Iterator<Item = Result<BlockRecord>> + '_ {
+        (u32::from(from)..=u32::from(to)).map(move |chain_length| {
+            let chain_length = ChainLength::from(chain_length);
+            let block_id = self.get_block_at(chain_length)?.ok_or_else(|| {
+                ErrorKind::CorruptedRecord(format!("chain length {}", u32::from(chain_length)))
+            })?;
+            self.expect_record(&block_id)
+        })
+    }
+
+    /// Replace the checkpoint with the indexes at the given block, which
+    /// must be on the longest chain
+    pub(super) fn put_checkpoint(&self, block_id: &HeaderHash, state: &State) -> Result<()> {
+        let mut bytes = hash_bytes(block_id)?;
+        write_state(&mut bytes, state)?;
+        self.db.insert(CHECKPOINT_KEY, bytes)?;
+        Ok(())
+    }
+
+    /// The block of the checkpoint and the indexes at this block, None if
+    /// no checkpoint was written yet
+    pub(super) fn get_checkpoint(&self) -> Result<Option<(HeaderHash, State)>> {
+        match self.db.get(CHECKPOINT_KEY)? {
+            None => Ok(None),
+            Some(bytes) => decode_checkpoint(bytes.as_ref())
+                .map(Some)
+                .map_err(|_| ErrorKind::CorruptedRecord("checkpoint".to_owned()).into()),
+        }
+    }
+
+    fn expect_record(&self, block_id: &HeaderHash) -> Result<BlockRecord> {
+        self.get_record(block_id)?
+            .ok_or_else(|| ErrorKind::CorruptedRecord(block_id.to_string()).into())
+    }
+}
+
+fn record_key(block_id: &HeaderHash) -> std::io::Result<Vec<u8>> {
+    let mut key = vec![RECORD_PREFIX];
+    block_id.serialize(&mut key)?;
+    Ok(key)
+}
+
+fn chain_length_key(chain_length: ChainLength) -> Vec<u8> {
+    let mut key = vec![CHAIN_LENGTH_PREFIX];
+    // big endian, so the keys are sorted by chain length
+    key.extend_from_slice(&u32::from(chain_length).to_be_bytes());
+    key
+}
+
+fn hash_bytes(hash: &HeaderHash) -> std::io::Result<Vec<u8>> {
+    hash.serialize_as_vec()
+}
+
+fn read_hash(bytes: &[u8]) -> std::result::Result<HeaderHash, ReadError> {
+    let mut buf = ReadBuf::from(bytes);
+    let hash = HeaderHash::read(&mut buf)?;
+    buf.expect_end()?;
+    Ok(hash)
+}
+
+fn encode_record(record: &BlockRecord) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_block(&mut bytes, &record.block)?;
+    write_fees(&mut bytes, &record.fees);
+    match &record.stake_distribution {
+        None => bytes.push(0),
+        Some(pool_stakes) => {
+            bytes.push(1);
+            write_pool_stakes(&mut bytes, pool_stakes)?;
+        }
+    }
+    Ok(bytes)
+}
+
+fn decode_record(bytes: &[u8]) -> std::result::Result<BlockRecord, ReadError> {
+    let mut buf = ReadBuf::from(bytes);
+    let block = read_block(&mut buf)?;
+    let fees = read_fees(&mut buf)?;
+    let stake_distribution = match buf.get_u8()? {
+        0 => None,
+        1 => Some(Arc::new(read_pool_stakes(&mut buf)?)),
+        tag => return Err(ReadError::UnknownTag(tag as u32)),
+    };
+    buf.expect_end()?;
+
+    Ok(BlockRecord {
+        block,
+        fees,
+        stake_distribution,
+    })
+}
+
+fn write_block(bytes: &mut Vec<u8>, block: &ExplorerBlock) -> std::io::Result<()> {
+    block.id().serialize(&mut *bytes)?;
+    block.parent_hash.serialize(&mut *bytes)?;
+    bytes.extend_from_slice(&block.date().epoch.to_be_bytes());
+    bytes.extend_from_slice(&block.date().slot_id.to_be_bytes());
+    bytes.extend_from_slice(&u32::from(block.chain_length()).to_be_bytes());
+    match block.producer() {
+        BlockProducer::None => bytes.push(0),
+        BlockProducer::StakePool(pool_id) => {
+            bytes.push(1);
+            pool_id.serialize(&mut *bytes)?;
+        }
+        BlockProducer::BftLeader(leader_id) => {
+            bytes.push(2);
+            leader_id.serialize(&mut *bytes)?;
+        }
+    }
+
+    bytes.extend_from_slice(&(block.transactions.len() as u32).to_be_bytes());
+    for tx in block.transactions.values() {
+        tx.id().serialize(&mut *bytes)?;
+
+        bytes.extend_from_slice(&(tx.inputs().len() as u32).to_be_bytes());
+        for input in tx.inputs() {
+            write_address(bytes, &input.address);
+            bytes.extend_from_slice(&input.value.0.to_be_bytes());
+            match &input.utxo {
+                None => bytes.push(0),
+                Some(utxo) => {
+                    bytes.push(1);
+                    write_utxo(bytes, utxo)?;
+                }
+            }
+        }
+
+        bytes.extend_from_slice(&(tx.outputs().len() as u32).to_be_bytes());
+        for output in tx.outputs() {
+            write_address(bytes, &output.address);
+            bytes.extend_from_slice(&output.value.0.to_be_bytes());
+        }
+
+        match &tx.certificate {
+            None => bytes.push(0),
+            Some(certificate) => {
+                bytes.push(1);
+                Certificate(certificate.clone()).serialize(&mut *bytes)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_block(buf: &mut ReadBuf) -> std::result::Result<ExplorerBlock, ReadError> {
+    let id = HeaderHash::read(buf)?;
+    let parent_hash = HeaderHash::read(buf)?;
+    let date = BlockDate {
+        epoch: buf.get_u32()?,
+        slot_id: buf.get_u32()?,
+    };
+    let chain_length = ChainLength::from(buf.get_u32()?);
+    let producer = match buf.get_u8()? {
+        0 => BlockProducer::None,
+        1 => BlockProducer::StakePool(PoolId::read(buf)?),
+        2 => BlockProducer::BftLeader(LeaderId::read(buf)?),
+        tag => return Err(ReadError::UnknownTag(tag as u32)),
+    };
+
+    let mut transactions = std::collections::HashMap::new();
+    for _ in 0..buf.get_u32()? {
+        let tx_id = HeaderHash::read(buf)?;
+
+        let mut inputs = Vec::new();
+        for _ in 0..buf.get_u32()? {
+            let address = read_address(buf)?;
+            let value = Value(buf.get_u64()?);
+            let utxo = match buf.get_u8()? {
+                0 => None,
+                1 => Some(read_utxo(buf)?),
+                tag => return Err(ReadError::UnknownTag(tag as u32)),
+            };
+            inputs.push(ExplorerInput {
+                address,
+                value,
+                utxo,
+            });
+        }
+
+        let mut outputs = Vec::new();
+        for _ in 0..buf.get_u32()? {
+            let address = read_address(buf)?;
+            let value = Value(buf.get_u64()?);
+            outputs.push(ExplorerOutput { address, value });
+        }
+
+        let certificate = match buf.get_u8()? {
+            0 => None,
+            1 => Some(Certificate::read(buf)?.0),
+            tag => return Err(ReadError::UnknownTag(tag as u32)),
+        };
+
+        transactions.insert(
+            tx_id,
+            ExplorerTransaction {
+                id: tx_id,
+                inputs,
+                outputs,
+                certificate,
+            },
+        );
+    }
+
+    Ok(ExplorerBlock {
+        transactions,
+        id,
+        date,
+        chain_length,
+        parent_hash,
+        producer,
+    })
+}
+
+fn write_fees(bytes: &mut Vec<u8>, fees: &LinearFee) {
+    bytes.extend_from_slice(&fees.constant.to_be_bytes());
+    bytes.extend_from_slice(&fees.coefficient.to_be_bytes());
+    bytes.extend_from_slice(&fees.certificate.to_be_bytes());
+}
+
+fn read_fees(buf: &mut ReadBuf) -> std::result::Result<LinearFee, ReadError> {
+    Ok(LinearFee::new(
+        buf.get_u64()?,
+        buf.get_u64()?,
+        buf.get_u64()?,
+    ))
+}
+
+fn write_pool_stakes(bytes: &mut Vec<u8>, pool_stakes: &PoolStakes) -> std::io::Result<()> {
+    write_entries(bytes, pool_stakes.iter(), |bytes, (pool_id, stake)| {
+        pool_id.serialize(&mut *bytes)?;
+        bytes.extend_from_slice(&stake.0.to_be_bytes());
+        Ok(())
+    })
+}
+
+fn read_pool_stakes(buf: &mut ReadBuf) -> std::result::Result<PoolStakes, ReadError> {
+    let mut pool_stakes = PoolStakes::new();
+    for _ in 0..buf.get_u32()? {
+        let pool_id = PoolId::read(buf)?;
+        pool_stakes.insert(pool_id, Value(buf.get_u64()?));
+    }
+    Ok(pool_stakes)
+}
+
+fn write_utxo(bytes: &mut Vec<u8>, utxo: &ExplorerUtxo) -> std::io::Result<()> {
+    utxo.transaction_id.serialize(&mut *bytes)?;
+    bytes.push(utxo.output_index);
+    bytes.extend_from_slice(&utxo.value.0.to_be_bytes());
+    Ok(())
+}
+
+fn read_utxo(buf: &mut ReadBuf) -> std::result::Result<ExplorerUtxo, ReadError> {
+    Ok(ExplorerUtxo {
+        transaction_id: HeaderHash::read(buf)?,
+        output_index: buf.get_u8()?,
+        value: Value(buf.get_u64()?),
+    })
+}
+
+/// Write the number of entries, then each of them with `write_entry`. The
+/// entries of the indexes are only known by iterating over them, so the
+/// number is written in place once they all are
+fn write_entries<I, F>(bytes: &mut Vec<u8>, entries: I, mut write_entry: F) -> std::io::Result<()>
+where
+    I: IntoIterator,
+    F: FnMut(&mut Vec<u8>, I::Item) -> std::io::Result<()>,
+{
+    let count_at = bytes.len();
+    bytes.extend_from_slice(&0u32.to_be_bytes());
+    let mut count: u32 = 0;
+    for entry in entries {
+        write_entry(bytes, entry)?;
+        count += 1;
+    }
+    bytes[count_at..count_at + 4].copy_from_slice(&count.to_be_bytes());
+    Ok(())
+}
+
+fn write_state(bytes: &mut Vec<u8>, state: &State) -> std::io::Result<()> {
+    write_fees(bytes, &state.fees);
+
+    write_entries(bytes, state.blocks.iter(), |bytes, (_, block)| {
+        write_block(bytes, block)
+    })?;
+
+    write_entries(
+        bytes,
+        state.transactions.iter(),
+        |bytes, (tx_id, block_id)| {
+            tx_id.serialize(&mut *bytes)?;
+            block_id.serialize(&mut *bytes)
+        },
+    )?;
+
+    write_entries(bytes, state.addresses.iter(), |bytes, (address, data)| {
+        write_address(bytes, address);
+        bytes.extend_from_slice(&data.balance.0.to_be_bytes());
+        match &data.delegation {
+            None => bytes.push(0),
+            Some(pool_id) => {
+                bytes.push(1);
+                pool_id.serialize(&mut *bytes)?;
+            }
+        }
+        write_entries(bytes, data.transactions.iter(), |bytes, tx_id| {
+            tx_id.serialize(&mut *bytes)
+        })?;
+        write_entries(bytes, data.utxos.iter(), write_utxo)
+    })?;
+
+    write_entries(bytes, state.epochs.iter(), |bytes, (epoch, data)| {
+        bytes.extend_from_slice(&epoch.to_be_bytes());
+        data.first_block.serialize(&mut *bytes)?;
+        data.last_block.serialize(&mut *bytes)?;
+        bytes.extend_from_slice(&data.total_blocks.to_be_bytes());
+        match &data.stake_distribution {
+            None => {
+                bytes.push(0);
+                Ok(())
+            }
+            Some(pool_stakes) => {
+                bytes.push(1);
+                write_pool_stakes(bytes, pool_stakes)
+            }
+        }
+    })?;
+
+    write_entries(
+        bytes,
+        state.chain_lengths.iter(),
+        |bytes, (chain_length, block_id)| {
+            bytes.extend_from_slice(&u32::from(*chain_length).to_be_bytes());
+            block_id.serialize(&mut *bytes)
+        },
+    )?;
+
+    write_entries(
+        bytes,
+        state.stake_pools.iter(),
+        |bytes, (pool_id, blocks)| {
+            pool_id.serialize(&mut *bytes)?;
+            write_entries(
+                bytes,
+                (0..blocks.len()).filter_map(|i| blocks.get(i)),
+                |bytes, block_id| block_id.serialize(&mut *bytes),
+            )
+        },
+    )
+}
+
+fn decode_checkpoint(bytes: &[u8]) -> std::result::Result<(HeaderHash, State), ReadError> {
+    let mut buf = ReadBuf::from(bytes);
+    let block_id = HeaderHash::read(&mut buf)?;
+    let fees = read_fees(&mut buf)?;
+
+    let mut blocks = Blocks::new();
+    for _ in 0..buf.get_u32()? {
+        let block = read_block(&mut buf)?;
+        blocks = blocks
+            .insert(block.id(), block)
+            .map_err(|_| duplicated_entry("block"))?;
+    }
+
+    let mut transactions = Transactions::new();
+    for _ in 0..buf.get_u32()? {
+        let tx_id = HeaderHash::read(&mut buf)?;
+        let tx_block_id = HeaderHash::read(&mut buf)?;
+        transactions = transactions
+            .insert(tx_id, tx_block_id)
+            .map_err(|_| duplicated_entry("transaction"))?;
+    }
+
+    let mut addresses = Addresses::new();
+    for _ in 0..buf.get_u32()? {
+        let address = read_address(&mut buf)?;
+        let balance = Value(buf.get_u64()?);
+        let delegation = match buf.get_u8()? {
+            0 => None,
+            1 => Some(PoolId::read(&mut buf)?),
+            tag => return Err(ReadError::UnknownTag(tag as u32)),
+        };
+        let mut address_transactions = Set::new();
+        for _ in 0..buf.get_u32()? {
+            address_transactions = address_transactions.add_element(HeaderHash::read(&mut buf)?);
+        }
+        let mut utxos = Set::new();
+        for _ in 0..buf.get_u32()? {
+            utxos = utxos.add_element(read_utxo(&mut buf)?);
+        }
+        let data = AddressData {
+            transactions: address_transactions,
+            balance,
+            utxos,
+            delegation,
+        };
+        addresses = addresses
+            .insert(address, data)
+            .map_err(|_| duplicated_entry("address"))?;
+    }
+
+    let mut epochs = Epochs::new();
+    for _ in 0..buf.get_u32()? {
+        let epoch = buf.get_u32()?;
+        let first_block = HeaderHash::read(&mut buf)?;
+        let last_block = HeaderHash::read(&mut buf)?;
+        let total_blocks = buf.get_u32()?;
+        let stake_distribution = match buf.get_u8()? {
+            0 => None,
+            1 => Some(Arc::new(read_pool_stakes(&mut buf)?)),
+            tag => return Err(ReadError::UnknownTag(tag as u32)),
+        };
+        let data = EpochData {
+            first_block,
+            last_block,
+            total_blocks,
+            stake_distribution,
+        };
+        epochs = epochs
+            .insert(epoch, data)
+            .map_err(|_| duplicated_entry("epoch"))?;
+    }
+
+    let mut chain_lengths = ChainLengths::new();
+    for _ in 0..buf.get_u32()? {
+        let chain_length = ChainLength::from(buf.get_u32()?);
+        let chain_length_block_id = HeaderHash::read(&mut buf)?;
+        chain_lengths = chain_lengths
+            .insert(chain_length, chain_length_block_id)
+            .map_err(|_| duplicated_entry("chain length"))?;
+    }
+
+    let mut stake_pools = StakePools::new();
+    for _ in 0..buf.get_u32()? {
+        let pool_id = PoolId::read(&mut buf)?;
+        let mut pool_blocks = PersistentSequence::new();
+        for _ in 0..buf.get_u32()? {
+            pool_blocks = pool_blocks.append(HeaderHash::read(&mut buf)?);
+        }
+        stake_pools = stake_pools
+            .insert(pool_id, pool_blocks)
+            .map_err(|_| duplicated_entry("stake pool"))?;
+    }
+
+    buf.expect_end()?;
+
+    let state = State {
+        transactions,
+        blocks,
+        addresses,
+        epochs,
+        chain_lengths,
+        stake_pools,
+        fees,
+    };
+    Ok((block_id, state))
+}
+
+fn duplicated_entry(index: &str) -> ReadError {
+    ReadError::StructureInvalid(format!("{} indexed twice in the checkpoint", index))
+}
+
+fn write_address(bytes: &mut Vec<u8>, address: &Address) {
+    let address_bytes = address.to_bytes();
+    bytes.push(address_bytes.len() as u8);
+    bytes.extend_from_slice(&address_bytes);
+}
+
+fn read_address(buf: &mut ReadBuf) -> std::result::Result<Address, ReadError> {
+    let len = buf.get_u8()? as usize;
+    let address_bytes = buf.get_slice(len)?;
+    Address::from_bytes(address_bytes)
+        .map_err(|err| ReadError::StructureInvalid(format!("invalid address: {:?}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_record, encode_record, read_hash, ExplorerStore};
+    use crate::blockcfg::{BlockDate, ChainLength, HeaderHash, Value};
+    use crate::explorer::indexing::{
+        BlockProducer, BlockRecord, ExplorerBlock, ExplorerOutput, ExplorerTransaction, PoolStakes,
+    };
+    use crate::explorer::State;
+    use chain_addr::{Address, Discrimination, Kind};
+    use chain_core::mempack::{ReadBuf, Readable as _};
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::certificate::PoolId;
+    use chain_impl_mockchain::fee::LinearFee;
+    use chain_impl_mockchain::leadership::bft::LeaderId;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::{fs, path::PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "jormungandr-explorer-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn hash(n: u8) -> HeaderHash {
+        read_hash(&[n; 32]).unwrap()
+    }
+
+    fn pool_id(n: u8) -> PoolId {
+        PoolId::read(&mut ReadBuf::from(&[n; 32][..])).unwrap()
+    }
+
+    /// the record of an empty block, its id and its parent's id made of
+    /// the given bytes
+    fn record(id: u8, parent: u8, chain_length: u32, producer: BlockProducer) -> BlockRecord {
+        BlockRecord {
+            block: ExplorerBlock {
+                transactions: HashMap::new(),
+                id: hash(id),
+                date: BlockDate {
+                    epoch: 0,
+                    slot_id: chain_length,
+                },
+                chain_length: ChainLength::from(chain_length),
+                parent_hash: hash(parent),
+                producer,
+            },
+            fees: LinearFee::new(1, 2, 3),
+            stake_distribution: None,
+        }
+    }
+
+    fn main_branch_ids(store: &ExplorerStore) -> Vec<HeaderHash> {
+        let tip = store.get_tip().unwrap().unwrap();
+        let tip_length = store
+            .get_record(&tip)
+            .unwrap()
+            .unwrap()
+            .block
+            .chain_length();
+        store
+            .main_branch(ChainLength::from(0), tip_length)
+            .map(|record| record.unwrap().block.id())
+            .collect()
+    }
+
+    #[test]
+    fn records_are_read_as_written() {
+        let leader_id =
+            LeaderId::from(SecretKey::<Ed25519>::generate(&mut rand::thread_rng()).to_public());
+        let mut pool_stakes = PoolStakes::new();
+        pool_stakes.insert(pool_id(7), Value(100));
+        let mut with_stakes = record(3, 2, 2, BlockProducer::None);
+        with_stakes.stake_distribution = Some(Arc::new(pool_stakes));
+
+        let records = vec![
+            record(1, 0, 0, BlockProducer::None),
+            record(2, 1, 1, BlockProducer::StakePool(pool_id(5))),
+            record(3, 2, 2, BlockProducer::BftLeader(leader_id.clone())),
+            with_stakes,
+        ];
+
+        for written in records {
+            let read = decode_record(&encode_record(&written).unwrap()).unwrap();
+            assert_eq!(read.block.id(), written.block.id());
+            assert_eq!(read.block.parent_hash, written.block.parent_hash);
+            assert_eq!(read.block.date(), written.block.date());
+            assert_eq!(read.block.chain_length(), written.block.chain_length());
+            match (read.block.producer(), written.block.producer()) {
+                (BlockProducer::None, BlockProducer::None) => (),
+                (BlockProducer::StakePool(read), BlockProducer::StakePool(written)) => {
+                    assert_eq!(read, written)
+                }
+                (BlockProducer::BftLeader(read), BlockProducer::BftLeader(written)) => {
+                    assert_eq!(read, written)
+                }
+                _ => panic!("the block producer changed"),
+            }
+            assert_eq!(read.fees.constant, written.fees.constant);
+            assert_eq!(read.fees.coefficient, written.fees.coefficient);
+            assert_eq!(read.fees.certificate, written.fees.certificate);
+            assert_eq!(
+                read.stake_distribution.as_ref().map(|stakes| stakes.len()),
+                written
+                    .stake_distribution
+                    .as_ref()
+                    .map(|stakes| stakes.len())
+            );
+            if let Some(stakes) = &written.stake_distribution {
+                for (pool_id, stake) in stakes.iter() {
+                    let read_stakes = read.stake_distribution.as_ref().unwrap();
+                    assert_eq!(read_stakes.get(pool_id).map(|value| value.0), Some(stake.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn main_branch_is_kept_after_reopening() {
+        let dir = temp_dir("reopen");
+        {
+            let store = ExplorerStore::open(&dir).unwrap();
+            for (id, parent, chain_length) in &[(1, 0, 0), (2, 1, 1), (3, 2, 2), (4, 3, 3)] {
+                store
+                    .put_record(&record(*id, *parent, *chain_length, BlockProducer::None))
+                    .unwrap();
+            }
+            store.set_tip(&hash(4)).unwrap();
+            store.flush().unwrap();
+        }
+
+        let store = ExplorerStore::open(&dir).unwrap();
+        assert_eq!(store.get_tip().unwrap(), Some(hash(4)));
+        assert_eq!(
+            main_branch_ids(&store),
+            vec![hash(1), hash(2), hash(3), hash(4)]
+        );
+
+        // switch to a longer branch forking after the block 2
+        for (id, parent, chain_length) in &[(5, 2, 2), (6, 5, 3), (7, 6, 4)] {
+            store
+                .put_record(&record(*id, *parent, *chain_length, BlockProducer::None))
+                .unwrap();
+        }
+        store.set_tip(&hash(7)).unwrap();
+        store.flush().unwrap();
+        drop(store);
+
+        let store = ExplorerStore::open(&dir).unwrap();
+        assert_eq!(store.get_tip().unwrap(), Some(hash(7)));
+        assert_eq!(
+            main_branch_ids(&store),
+            vec![hash(1), hash(2), hash(5), hash(6), hash(7)]
+        );
+
+        // and back to a shorter one, the entries past its tip are removed
+        store.set_tip(&hash(3)).unwrap();
+        store.flush().unwrap();
+        drop(store);
+
+        let store = ExplorerStore::open(&dir).unwrap();
+        assert_eq!(main_branch_ids(&store), vec![hash(1), hash(2), hash(3)]);
+        assert_eq!(store.get_block_at(ChainLength::from(3)).unwrap(), None);
+        drop(store);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn main_branch_is_read_from_the_given_chain_length() {
+        let dir = temp_dir("range");
+        let store = ExplorerStore::open(&dir).unwrap();
+        for (id, parent, chain_length) in &[(1, 0, 0), (2, 1, 1), (3, 2, 2), (4, 3, 3)] {
+            store
+                .put_record(&record(*id, *parent, *chain_length, BlockProducer::None))
+                .unwrap();
+        }
+        store.set_tip(&hash(4)).unwrap();
+
+        let ids: Vec<_> = store
+            .main_branch(ChainLength::from(2), ChainLength::from(3))
+            .map(|record| record.unwrap().block.id())
+            .collect();
+        assert_eq!(ids, vec![hash(3), hash(4)]);
+
+        // a chain length past the tip is not in the index
+        assert!(store
+            .main_branch(ChainLength::from(4), ChainLength::from(4))
+            .all(|record| record.is_err()));
+        drop(store);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_is_read_as_written() {
+        let dir = temp_dir("checkpoint");
+        let address = Address(
+            Discrimination::Test,
+            Kind::Single(SecretKey::<Ed25519>::generate(&mut rand::thread_rng()).to_public()),
+        );
+        let mut with_output = record(2, 1, 1, BlockProducer::None);
+        with_output.block.transactions.insert(
+            hash(9),
+            ExplorerTransaction {
+                id: hash(9),
+                inputs: Vec::new(),
+                outputs: vec![ExplorerOutput {
+                    address: address.clone(),
+                    value: Value(42),
+                }],
+                certificate: None,
+            },
+        );
+
+        let state = [record(1, 0, 0, BlockProducer::None), with_output]
+            .iter()
+            .fold(State::new(LinearFee::new(1, 2, 3)), |state, record| {
+                state.apply_record(record, Discrimination::Test).unwrap()
+            });
+
+        {
+            let store = ExplorerStore::open(&dir).unwrap();
+            assert!(store.get_checkpoint().unwrap().is_none());
+            store.put_checkpoint(&hash(2), &state).unwrap();
+            store.flush().unwrap();
+        }
+
+        let store = ExplorerStore::open(&dir).unwrap();
+        let (block_id, read) = store.get_checkpoint().unwrap().unwrap();
+        assert_eq!(block_id, hash(2));
+        assert_eq!(
+            read.blocks.lookup(&hash(2)).map(|block| block.parent_hash),
+            Some(hash(1))
+        );
+        assert_eq!(
+            read.chain_lengths.lookup(&ChainLength::from(1)),
+            Some(&hash(2))
+        );
+        assert_eq!(read.transactions.lookup(&hash(9)), Some(&hash(2)));
+        let epoch = read.epochs.lookup(&0).unwrap();
+        assert_eq!(epoch.first_block, hash(1));
+        assert_eq!(epoch.last_block, hash(2));
+        assert_eq!(epoch.total_blocks, 1);
+        let address_data = read.addresses.lookup(&address).unwrap();
+        assert_eq!(address_data.balance.0, 42);
+        assert_eq!(
+            address_data.transactions.iter().collect::<Vec<_>>(),
+            vec![&hash(9)]
+        );
+        assert_eq!(address_data.utxos.iter().count(), 1);
+        assert_eq!(read.fees.constant, 1);
+        drop(store);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -19,6 +19,15 @@ impl<T: Hash + PartialEq + Eq + Clone> HamtSet<T> {
         HamtSet(new_hamt)
     }
 
+    pub fn remove_element(&self, element: &T) -> HamtSet<T> {
+        let new_hamt = match self.0.remove(element) {
+            Ok(new_hamt) => new_hamt,
+            Err(_) => self.0.clone(),
+        };
+
+        HamtSet(new_hamt)
+    }
+
     pub fn iter(&self) -> HamtSetIter<T> {
         HamtSetIter(self.0.iter())
     }
@@ -2,7 +2,9 @@ use crate::{
     blockcfg::{Value, ValueError},
     fragment::{Fragment, FragmentId},
 };
-use std::time::SystemTime;
+use chain_addr::Address;
+use chain_impl_mockchain::transaction::Transaction;
+use std::{cmp::Ordering, time::SystemTime};
 
 pub struct PoolEntry {
     // reference of the fragment stored in the pool
@@ -33,11 +35,7 @@ impl PoolEntry {
         let raw = fragment.to_raw();
         let fragment_size = raw.size_bytes_plus_size();
         let fragment_ref = raw.id();
-        // TODO: the fragment fee is not yet computed. Yet we should
-        // have an explicit fee in the message. So we need to be able
-        // to extract this information without the need to compute the
-        // fee from the ledger's fee settings.
-        let fragment_fee = Value::zero();
+        let fragment_fee = fragment_fee(fragment);
 
         PoolEntry {
             fragment_ref: fragment_ref,
@@ -74,6 +72,10 @@ impl PoolEntry {
         &self.received_at
     }
     #[inline]
+    pub fn fee_per_byte(&self) -> FeePerByte {
+        FeePerByte::new(self.fragment_fee.0, self.fragment_size)
+    }
+    #[inline]
     pub fn with_descendants_fee(&self) -> Result<Value, ValueError> {
         self.descendants_fee + self.fragment_fee
     }
@@ -90,3 +92,71 @@ impl PoolEntry {
         self.ancestors_size + self.fragment_size
     }
 }
+
+/// fee of a fragment relative to its size, compared without loss of precision
+#[derive(Clone, Copy, Debug)]
+pub struct FeePerByte {
+    fee: u64,
+    size: u64,
+}
+
+impl FeePerByte {
+    pub fn new(fee: u64, size: usize) -> Self {
+        FeePerByte {
+            fee,
+            size: size as u64,
+        }
+    }
+}
+
+impl Ord for FeePerByte {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lhs = self.fee as u128 * other.size as u128;
+        let rhs = other.fee as u128 * self.size as u128;
+        lhs.cmp(&rhs)
+    }
+}
+
+impl PartialOrd for FeePerByte {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for FeePerByte {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FeePerByte {}
+
+/// the fee explicitly paid by the fragment: the difference between
+/// its inputs and its outputs
+///
+/// The fee is not computed from the fee settings of the `LedgerParameters`:
+/// the ledger only accepts a transaction whose inputs minus outputs equals
+/// the fee computed from these settings, so both are the same for the valid
+/// fragments. The difference is known when the fragment enters the pool,
+/// without the ledger parameters of the epoch the fragment will be selected
+/// in, and a fragment overpaying its fee is ranked by what it really pays.
+fn fragment_fee(fragment: &Fragment) -> Value {
+    match fragment {
+        Fragment::Transaction(auth_tx) => transaction_fee(&auth_tx.transaction),
+        Fragment::OwnerStakeDelegation(auth_tx) => transaction_fee(&auth_tx.transaction),
+        Fragment::StakeDelegation(auth_tx) => transaction_fee(&auth_tx.transaction),
+        Fragment::PoolRegistration(auth_tx) => transaction_fee(&auth_tx.transaction),
+        Fragment::PoolManagement(auth_tx) => transaction_fee(&auth_tx.transaction),
+        _ => Value::zero(),
+    }
+}
+
+fn transaction_fee<Extra>(transaction: &Transaction<Address, Extra>) -> Value {
+    let input_sum = Value::sum(transaction.inputs.iter().map(|input| input.value));
+    let output_sum = Value::sum(transaction.outputs.iter().map(|output| output.value));
+    // Input < output implies minting, so no fee
+    match (input_sum, output_sum) {
+        (Ok(input_sum), Ok(output_sum)) => (input_sum - output_sum).unwrap_or(Value::zero()),
+        _ => Value::zero(),
+    }
+}
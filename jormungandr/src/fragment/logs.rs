@@ -1,4 +1,5 @@
-use crate::fragment::FragmentId;
+use crate::{fragment::FragmentId, utils::broadcast::Broadcaster};
+use futures::sync::mpsc::Receiver;
 use jormungandr_lib::interfaces::{FragmentLog, FragmentStatus};
 use std::time::Duration;
 use tokio::{
@@ -8,11 +9,23 @@ use tokio::{
 };
 
 #[derive(Clone)]
-pub struct Logs(Lock<internal::Logs>);
+pub struct Logs {
+    inner: Lock<internal::Logs>,
+    updates: Broadcaster<FragmentLog>,
+}
 
 impl Logs {
     pub fn new(ttl: Duration) -> Self {
-        Logs(Lock::new(internal::Logs::new(ttl)))
+        let updates = Broadcaster::new();
+        Logs {
+            inner: Lock::new(internal::Logs::new(ttl, updates.clone())),
+            updates,
+        }
+    }
+
+    /// Returns the stream of the logs inserted or modified from now on
+    pub fn subscribe(&self) -> Receiver<FragmentLog> {
+        self.updates.subscribe()
     }
 
     /// Returns true if fragment was registered
@@ -40,6 +53,22 @@ impl Logs {
         self.run_on_inner(move |inner| inner.exist_all(hashes))
     }
 
+    pub fn get(
+        &self,
+        fragment_id: FragmentId,
+    ) -> impl Future<Item = Option<FragmentLog>, Error = ()> {
+        self.run_on_inner(move |inner| inner.get(&fragment_id.into()).cloned())
+    }
+
+    /// Returns the logs of the known fragments among the given ones
+    pub fn get_all(
+        &self,
+        fragment_ids: impl IntoIterator<Item = FragmentId>,
+    ) -> impl Future<Item = Vec<FragmentLog>, Error = ()> {
+        let hashes = fragment_ids.into_iter().map(Into::into);
+        self.run_on_inner(move |inner| inner.get_all(hashes))
+    }
+
     pub fn modify(
         &mut self,
         fragment_id: FragmentId,
@@ -74,6 +103,19 @@ impl Logs {
         self.run_on_inner(move |inner| inner.logs().cloned().collect())
     }
 
+    /// Returns the number of logs of each status
+    pub fn status_counts(&self) -> impl Future<Item = StatusCounts, Error = ()> {
+        self.run_on_inner(move |inner| inner.status_counts())
+    }
+
+    /// Returns the logs matching the given filter
+    pub fn logs_filtered<F>(&self, filter: F) -> impl Future<Item = Vec<FragmentLog>, Error = ()>
+    where
+        F: Fn(&FragmentLog) -> bool,
+    {
+        self.run_on_inner(move |inner| inner.logs().filter(|log| filter(log)).cloned().collect())
+    }
+
     fn run_on_inner<O>(
         &self,
         run: impl FnOnce(&mut internal::Logs) -> O,
@@ -83,12 +125,42 @@ impl Logs {
     }
 
     pub(super) fn inner<E>(&self) -> impl Future<Item = LockGuard<internal::Logs>, Error = E> {
-        let mut lock = self.0.clone();
+        let mut lock = self.inner.clone();
         future::poll_fn(move || Ok(lock.poll_lock()))
     }
 }
 
+/// number of logs of each status, kept up to date as the logs change
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatusCounts {
+    pub pending: usize,
+    pub rejected: usize,
+    pub in_a_block: usize,
+    pub evicted: usize,
+}
+
+impl StatusCounts {
+    fn count_mut(&mut self, status: &FragmentStatus) -> &mut usize {
+        match status {
+            FragmentStatus::Pending => &mut self.pending,
+            FragmentStatus::Rejected { .. } => &mut self.rejected,
+            FragmentStatus::InABlock { .. } => &mut self.in_a_block,
+            FragmentStatus::Evicted { .. } => &mut self.evicted,
+        }
+    }
+
+    fn add(&mut self, status: &FragmentStatus) {
+        *self.count_mut(status) += 1;
+    }
+
+    fn remove(&mut self, status: &FragmentStatus) {
+        *self.count_mut(status) -= 1;
+    }
+}
+
 pub(super) mod internal {
+    use super::StatusCounts;
+    use crate::utils::broadcast::Broadcaster;
     use jormungandr_lib::{
         crypto::hash::Hash,
         interfaces::{FragmentLog, FragmentOrigin, FragmentStatus},
@@ -106,14 +178,18 @@ pub(super) mod internal {
         entries: HashMap<Hash, (FragmentLog, delay_queue::Key)>,
         expirations: DelayQueue<Hash>,
         ttl: Duration,
+        updates: Broadcaster<FragmentLog>,
+        status_counts: StatusCounts,
     }
 
     impl Logs {
-        pub fn new(ttl: Duration) -> Self {
+        pub fn new(ttl: Duration, updates: Broadcaster<FragmentLog>) -> Self {
             Logs {
                 entries: HashMap::new(),
                 expirations: DelayQueue::new(),
                 ttl,
+                updates,
+                status_counts: StatusCounts::default(),
             }
         }
 
@@ -128,6 +204,17 @@ pub(super) mod internal {
                 .collect()
         }
 
+        pub fn get(&self, fragment_id: &Hash) -> Option<&FragmentLog> {
+            self.entries.get(fragment_id).map(|(log, _)| log)
+        }
+
+        pub fn get_all(&self, fragment_ids: impl IntoIterator<Item = Hash>) -> Vec<FragmentLog> {
+            fragment_ids
+                .into_iter()
+                .filter_map(|fragment_id| self.get(&fragment_id).cloned())
+                .collect()
+        }
+
         /// Returns true if fragment was registered
         pub fn insert(&mut self, log: FragmentLog) -> bool {
             let fragment_id = *log.fragment_id();
@@ -136,6 +223,8 @@ pub(super) mod internal {
                 Entry::Vacant(entry) => entry,
             };
             let delay = self.expirations.insert(fragment_id, self.ttl);
+            self.status_counts.add(log.status());
+            self.updates.send(log.clone());
             entry.insert((log, delay));
             true
         }
@@ -151,10 +240,13 @@ pub(super) mod internal {
         pub fn modify(&mut self, fragment_id: &Hash, status: FragmentStatus) {
             match self.entries.entry(fragment_id.clone()) {
                 Entry::Occupied(mut entry) => {
+                    self.status_counts.remove(entry.get().0.status());
+                    self.status_counts.add(&status);
                     entry.get_mut().0.modify(status);
 
                     self.expirations
                         .reset_at(&entry.get().1, Instant::now() + self.ttl);
+                    self.updates.send(entry.get().0.clone());
                 }
                 Entry::Vacant(entry) => {
                     // while a log modification, if the log was not already present in the
@@ -163,17 +255,24 @@ pub(super) mod internal {
                     // be stored.
 
                     let delay = self.expirations.insert(*fragment_id, self.ttl);
-                    entry.insert((
-                        FragmentLog::new(fragment_id.clone().into_hash(), FragmentOrigin::Network),
-                        delay,
-                    ));
+                    let log = FragmentLog::new(
+                        fragment_id.clone().into_hash(),
+                        FragmentOrigin::Network {
+                            node_id: None,
+                            address: None,
+                        },
+                    );
+                    self.status_counts.add(log.status());
+                    self.updates.send(log.clone());
+                    entry.insert((log, delay));
                 }
             }
         }
 
         pub fn remove(&mut self, fragment_id: &Hash) {
-            if let Some((_, cache_key)) = self.entries.remove(fragment_id) {
+            if let Some((log, cache_key)) = self.entries.remove(fragment_id) {
                 self.expirations.remove(&cache_key);
+                self.status_counts.remove(log.status());
             }
         }
 
@@ -183,7 +282,9 @@ pub(super) mod internal {
                     Async::NotReady => return Ok(Async::Ready(())),
                     Async::Ready(None) => return Ok(Async::Ready(())),
                     Async::Ready(Some(entry)) => {
-                        self.entries.remove(entry.get_ref());
+                        if let Some((log, _)) = self.entries.remove(entry.get_ref()) {
+                            self.status_counts.remove(log.status());
+                        }
                     }
                 }
             }
@@ -192,5 +293,61 @@ pub(super) mod internal {
         pub fn logs<'a>(&'a self) -> impl Iterator<Item = &'a FragmentLog> {
             self.entries.values().map(|(v, _)| v)
         }
+
+        pub fn status_counts(&self) -> StatusCounts {
+            self.status_counts
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{internal, StatusCounts};
+    use crate::utils::broadcast::Broadcaster;
+    use jormungandr_lib::{
+        crypto::hash::Hash,
+        interfaces::{FragmentLog, FragmentOrigin, FragmentStatus},
+    };
+    use std::time::Duration;
+    use tokio::{prelude::future, runtime::current_thread::Runtime};
+
+    fn log(byte: u8) -> FragmentLog {
+        FragmentLog::new(Hash::from([byte; 32]).into_hash(), FragmentOrigin::Rest)
+    }
+
+    fn rejected() -> FragmentStatus {
+        FragmentStatus::Rejected {
+            reason: "invalid".to_owned(),
+        }
+    }
+
+    #[test]
+    fn status_counts_follow_the_logs() {
+        let mut runtime = Runtime::new().unwrap();
+        let counts = runtime
+            .block_on(future::lazy(|| {
+                let mut logs = internal::Logs::new(Duration::from_secs(3600), Broadcaster::new());
+                let mut counts = Vec::new();
+
+                logs.insert_all(vec![log(1), log(2), log(3)]);
+                counts.push(logs.status_counts());
+
+                logs.modify(&Hash::from([1; 32]), rejected());
+                counts.push(logs.status_counts());
+
+                logs.remove(&Hash::from([2; 32]));
+                logs.remove(&Hash::from([1; 32]));
+                counts.push(logs.status_counts());
+
+                future::ok::<_, ()>(counts)
+            }))
+            .unwrap();
+
+        let pending = |pending, rejected| StatusCounts {
+            pending,
+            rejected,
+            ..StatusCounts::default()
+        };
+        assert_eq!(counts, vec![pending(3, 0), pending(2, 1), pending(1, 0)]);
     }
 }
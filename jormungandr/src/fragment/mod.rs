@@ -5,8 +5,8 @@ mod process;
 pub mod selection;
 
 pub use self::entry::PoolEntry;
-pub use self::logs::Logs;
-pub use self::pool::Pool;
+pub use self::logs::{Logs, StatusCounts};
+pub use self::pool::{Pool, PoolLimits};
 pub use self::process::Process;
 
 pub use crate::blockcfg::{Fragment, FragmentId};
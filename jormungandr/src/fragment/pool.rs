@@ -1,14 +1,23 @@
 use crate::{
     blockcfg::{HeaderContentEvalContext, Ledger, LedgerParameters},
-    fragment::{selection::FragmentSelectionAlgorithm, Fragment, FragmentId, Logs},
+    blockchain::{Ref, Tip},
+    fragment::{
+        selection::{rejection_reason, FragmentSelectionAlgorithm},
+        Fragment, FragmentId, Logs,
+    },
     intercom::{NetworkMsg, PropagateMsg},
+    network::p2p::{comm::Misbehavior, topology::NodeId},
+    settings::start::config::EvictionPolicy,
+    stats_counter::StatsCounter,
     utils::async_msg::MessageBox,
 };
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::transaction::AuthenticatedTransaction;
-use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin, FragmentStatus};
+use jormungandr_lib::interfaces::{
+    FragmentLog, FragmentOrigin, FragmentStatus, FragmentSubmission,
+};
 use slog::Logger;
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 use tokio::{
     prelude::{
         future::{
@@ -21,19 +30,40 @@ use tokio::{
     timer,
 };
 
+/// bounds of the pool, pending fragments are evicted to stay within them
+#[derive(Clone, Copy, Debug)]
+pub struct PoolLimits {
+    /// maximum number of pending fragments
+    pub max_entries: Option<usize>,
+    /// maximum total size of the pending fragments, in bytes
+    pub max_bytes: Option<usize>,
+    pub eviction_policy: EvictionPolicy,
+}
+
 #[derive(Clone)]
 pub struct Pool {
     logs: Logs,
     pool: Lock<internal::Pool>,
     network_msg_box: MessageBox<NetworkMsg>,
+    tip: Tip,
+    stats_counter: StatsCounter,
 }
 
 impl Pool {
-    pub fn new(ttl: Duration, logs: Logs, network_msg_box: MessageBox<NetworkMsg>) -> Self {
+    pub fn new(
+        ttl: Duration,
+        limits: PoolLimits,
+        logs: Logs,
+        network_msg_box: MessageBox<NetworkMsg>,
+        tip: Tip,
+        stats_counter: StatsCounter,
+    ) -> Self {
         Pool {
             logs,
-            pool: Lock::new(internal::Pool::new(ttl)),
+            pool: Lock::new(internal::Pool::new(ttl, limits)),
             network_msg_box,
+            tip,
+            stats_counter,
         }
     }
 
@@ -41,36 +71,112 @@ impl Pool {
         &self.logs
     }
 
-    /// Returns number of registered fragments
+    /// Returns the outcome of the submission of each fragment
+    ///
+    /// The fragments are checked against the ledger at the tip of the
+    /// blockchain with the fragments accepted since then applied, kept by
+    /// the pool from one submission to the next. The invalid ones are
+    /// logged as rejected and are neither pooled nor propagated. The network
+    /// peer which sent the fragments, if any, is reported to the network task
+    /// once for all the fragments invalid on their own: the fragments
+    /// rejected by the ledger may have been relayed in good faith.
     pub fn insert_and_propagate_all(
         &mut self,
         origin: FragmentOrigin,
-        mut fragments: Vec<Fragment>,
+        sender: Option<NodeId>,
+        fragments: Vec<Fragment>,
         logger: Logger,
-    ) -> impl Future<Item = usize, Error = ()> {
-        fragments.retain(is_fragment_valid);
+    ) -> impl Future<Item = Vec<FragmentSubmission>, Error = ()> {
+        let mut submissions = Vec::with_capacity(fragments.len());
+        let fragments = fragments
+            .into_iter()
+            .filter_map(|fragment| match check_fragment(&fragment) {
+                Ok(()) => Some(fragment),
+                Err(reason) => {
+                    submissions.push(FragmentSubmission::rejected(fragment.id().into(), reason));
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut network_msg_box = self.network_msg_box.clone();
+        if let Some(node_id) = sender.filter(|_| !submissions.is_empty()) {
+            network_msg_box
+                .try_send(NetworkMsg::ReportMisbehavior(
+                    node_id,
+                    Misbehavior::InvalidFragments(submissions.len() as u32),
+                ))
+                .unwrap_or_else(|err| error!(logger, "cannot report peer to network: {}", err));
+        }
         if fragments.is_empty() {
-            return A(future::ok(0));
+            return A(future::ok(submissions));
         }
         let mut pool_lock = self.pool.clone();
         let mut logs = self.logs.clone();
-        let network_msg_box = self.network_msg_box.clone();
+        let stats_counter = self.stats_counter.clone();
         let fragment_ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
         let fragments_exist_in_logs = self.logs.exist_all(fragment_ids);
-        B(
-            fragments_exist_in_logs.and_then(move |fragments_exist_in_logs| {
+        let tip_reference = self
+            .tip
+            .get_ref()
+            .map_err(|_: std::convert::Infallible| unreachable!());
+        B(fragments_exist_in_logs.join(tip_reference).and_then(
+            move |(fragments_exist_in_logs, tip_reference)| {
                 future::poll_fn(move || Ok(pool_lock.poll_lock())).and_then(move |mut pool| {
-                    let new_fragments = fragments
-                        .into_iter()
-                        .zip(fragments_exist_in_logs)
-                        .filter(|(_, exists_in_logs)| !exists_in_logs)
-                        .map(|(fragment, _)| fragment);
-                    let new_fragments = pool.insert_all(new_fragments);
-                    let count = new_fragments.len();
-                    let fragment_logs = new_fragments
+                    let mut new_fragments = Vec::with_capacity(fragments.len());
+                    for (fragment, exists_in_logs) in
+                        fragments.into_iter().zip(fragments_exist_in_logs)
+                    {
+                        if exists_in_logs {
+                            submissions.push(FragmentSubmission::rejected(
+                                fragment.id().into(),
+                                ALREADY_KNOWN_REASON,
+                            ));
+                        } else {
+                            new_fragments.push(fragment);
+                        }
+                    }
+                    let (new_fragments, invalid) =
+                        check_against_ledger(&tip_reference, &mut pool, new_fragments);
+                    let mut fragment_logs = Vec::with_capacity(new_fragments.len() + invalid.len());
+                    for (fragment_id, reason) in invalid {
+                        let mut log = FragmentLog::new(fragment_id.into(), origin);
+                        log.modify(FragmentStatus::Rejected {
+                            reason: reason.clone(),
+                        });
+                        fragment_logs.push(log);
+                        submissions.push(FragmentSubmission::rejected(fragment_id.into(), reason));
+                    }
+                    let new_fragment_ids =
+                        new_fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+                    let (new_fragments, evicted) = pool.insert_all(new_fragments);
+                    stats_counter.set_mempool_size(pool.len());
+                    fragment_logs.extend(
+                        new_fragments
+                            .iter()
+                            .map(|fragment| FragmentLog::new(fragment.id().into(), origin)),
+                    );
+                    let inserted_ids = new_fragments
                         .iter()
-                        .map(move |fragment| FragmentLog::new(fragment.id().into(), origin))
+                        .map(Fragment::id)
+                        .collect::<HashSet<_>>();
+                    // the new fragments may have been evicted right away
+                    // if they were the first candidates for eviction
+                    submissions.extend(new_fragment_ids.into_iter().map(|fragment_id| {
+                        if pool.contains(&fragment_id) {
+                            FragmentSubmission::accepted(fragment_id.into())
+                        } else if inserted_ids.contains(&fragment_id) {
+                            FragmentSubmission::rejected(fragment_id.into(), POOL_FULL_REASON)
+                        } else {
+                            FragmentSubmission::rejected(fragment_id.into(), ALREADY_KNOWN_REASON)
+                        }
+                    }));
+                    let new_fragments = new_fragments
+                        .into_iter()
+                        .filter(|fragment| pool.contains(&fragment.id()))
                         .collect::<Vec<_>>();
+                    if !evicted.is_empty() {
+                        debug!(logger, "evicted fragments from the full pool"; "count" => evicted.len());
+                    }
                     stream::iter_ok(new_fragments)
                         .map(|fragment| NetworkMsg::Propagate(PropagateMsg::Fragment(fragment)))
                         .fold(network_msg_box, |network_msg_box, fragment_msg| {
@@ -79,11 +185,11 @@ impl Pool {
                         .map_err(move |err: <MessageBox<_> as Sink>::SinkError| {
                             error!(logger, "cannot propagate fragment to network: {}", err)
                         })
-                        .and_then(move |_| logs.insert_all(fragment_logs))
-                        .map(move |_| count)
+                        .and_then(move |_| update_logs(&mut logs, fragment_logs, evicted))
+                        .map(move |_| submissions)
                 })
-            }),
-        )
+            },
+        ))
     }
 
     pub fn remove_added_to_block(
@@ -93,9 +199,11 @@ impl Pool {
     ) -> impl Future<Item = (), Error = ()> {
         let mut pool_lock = self.pool.clone();
         let mut logs = self.logs.clone();
+        let stats_counter = self.stats_counter.clone();
         future::poll_fn(move || Ok(pool_lock.poll_lock()))
             .map(move |mut pool| {
                 pool.remove_all(fragment_ids.iter().cloned());
+                stats_counter.set_mempool_size(pool.len());
                 fragment_ids
             })
             .and_then(move |fragment_ids| logs.modify_all(fragment_ids, status))
@@ -104,8 +212,15 @@ impl Pool {
     pub fn poll_purge(&mut self) -> impl Future<Item = (), Error = timer::Error> {
         let mut lock = self.pool.clone();
         let purge_logs = self.logs.poll_purge();
+        let stats_counter = self.stats_counter.clone();
         future::poll_fn(move || Ok(lock.poll_lock()))
-            .and_then(move |mut guard| future::poll_fn(move || guard.poll_purge()))
+            .and_then(move |mut guard| {
+                future::poll_fn(move || {
+                    try_ready!(guard.poll_purge());
+                    stats_counter.set_mempool_size(guard.len());
+                    Ok(Async::Ready(()))
+                })
+            })
             .and_then(move |()| purge_logs)
     }
 
@@ -121,88 +236,202 @@ impl Pool {
     {
         let mut lock = self.pool.clone();
         let logs = self.logs().clone();
+        let stats_counter = self.stats_counter.clone();
 
         // FIXME deadlock hazard, nested pool lock and logs lock
         future::poll_fn(move || Ok(lock.poll_lock()))
             .and_then(move |pool| logs.inner().map(|logs| (pool, logs)))
             .and_then(move |(mut pool, mut logs)| {
                 selection_alg.select(&ledger, &ledger_params, &metadata, &mut logs, &mut pool);
+                stats_counter.set_mempool_size(pool.len());
                 future::ok(selection_alg)
             })
     }
 }
 
-fn is_fragment_valid(fragment: &Fragment) -> bool {
+const ALREADY_KNOWN_REASON: &str = "the fragment is already known to the node";
+const POOL_FULL_REASON: &str = "the fragment pool is full";
+
+/// log the new fragments, then the fragments evicted from the pool, some of
+/// the new fragments may be among the evicted ones
+fn update_logs(
+    logs: &mut Logs,
+    new_logs: Vec<FragmentLog>,
+    evicted: Vec<FragmentId>,
+) -> impl Future<Item = (), Error = ()> {
+    let mut evicted_logs = logs.clone();
+    logs.insert_all(new_logs).and_then(move |_| {
+        evicted_logs.modify_all(
+            evicted,
+            FragmentStatus::Evicted {
+                reason: POOL_FULL_REASON.to_owned(),
+            },
+        )
+    })
+}
+
+/// check the fragment is valid on its own, independently of the ledger state
+fn check_fragment(fragment: &Fragment) -> Result<(), String> {
     match fragment {
-        Fragment::Transaction(ref tx) => is_transaction_valid(tx),
-        _ => true,
+        Fragment::Transaction(ref tx) => check_transaction(tx),
+        _ => Ok(()),
     }
 }
 
-fn is_transaction_valid<A, E>(tx: &AuthenticatedTransaction<A, E>) -> bool {
-    tx.transaction.verify_possibly_balanced().is_ok()
+/// check the fragments against the ledger at the tip of the blockchain with
+/// the pending fragments of the pool applied, so a fragment may spend the
+/// outputs of a pending fragment or follow the account spending counter of
+/// the pending fragments. Each valid fragment is applied to the pending
+/// ledger of the pool, so a fragment may also depend on another one
+/// submitted alongside it.
+///
+/// Returns the valid fragments and the IDs of the invalid ones with the
+/// reason of their rejection.
+fn check_against_ledger(
+    tip_reference: &Ref,
+    pool: &mut internal::Pool,
+    fragments: Vec<Fragment>,
+) -> (Vec<Fragment>, Vec<(FragmentId, String)>) {
+    let ledger_params = tip_reference.epoch_ledger_parameters();
+    let metadata = HeaderContentEvalContext {
+        block_date: tip_reference.block_date(),
+        chain_length: tip_reference.chain_length().increase(),
+        nonce: None,
+    };
+    let ledger = pool.pending_ledger(tip_reference);
+    let mut valid = Vec::with_capacity(fragments.len());
+    let mut invalid = Vec::new();
+    for fragment in fragments {
+        match ledger.apply_fragment(ledger_params, &fragment, &metadata) {
+            Ok(ledger_new) => {
+                *ledger = ledger_new;
+                valid.push(fragment);
+            }
+            Err(error) => invalid.push((fragment.id(), rejection_reason(error))),
+        }
+    }
+    (valid, invalid)
+}
+
+fn check_transaction<A, E>(tx: &AuthenticatedTransaction<A, E>) -> Result<(), String> {
+    tx.transaction
+        .verify_possibly_balanced()
+        .map_err(|error| format!("the transaction is not balanced: {}", error))
 }
 
 pub(super) mod internal {
     use super::*;
-    use crate::fragment::PoolEntry;
-    use std::{
-        collections::{hash_map::Entry, HashMap, VecDeque},
-        sync::Arc,
-    };
+    use crate::blockcfg::HeaderHash;
+    use crate::fragment::{entry::FeePerByte, PoolEntry};
+    use linked_hash_map::LinkedHashMap;
+    use std::{collections::BTreeMap, sync::Arc};
     use tokio::timer::{delay_queue, DelayQueue};
 
     pub struct Pool {
-        entries: HashMap<FragmentId, (Arc<PoolEntry>, Fragment, delay_queue::Key)>,
-        entries_by_time: VecDeque<FragmentId>,
+        /// the pending fragments, in the order they were received, along
+        /// with their arrival sequence number
+        entries: LinkedHashMap<FragmentId, (Arc<PoolEntry>, Fragment, delay_queue::Key, u64)>,
+        /// the pending fragments ordered by fee per byte, then by arrival
+        by_fee: BTreeMap<(FeePerByte, u64), FragmentId>,
+        /// the arrival sequence number of the next inserted fragment
+        next_seq: u64,
         expirations: DelayQueue<FragmentId>,
         ttl: Duration,
+        limits: PoolLimits,
+        /// total size of the pending fragments, in bytes
+        total_size: usize,
+        /// the ledger at the given tip with the fragments accepted since
+        /// then applied, see `pending_ledger`
+        pending_ledger: Option<(HeaderHash, Ledger)>,
     }
 
     impl Pool {
-        pub fn new(ttl: Duration) -> Self {
+        pub fn new(ttl: Duration, limits: PoolLimits) -> Self {
             Pool {
-                entries: HashMap::new(),
-                entries_by_time: VecDeque::new(),
+                entries: LinkedHashMap::new(),
+                by_fee: BTreeMap::new(),
+                next_seq: 0,
                 expirations: DelayQueue::new(),
                 ttl,
+                limits,
+                total_size: 0,
+                pending_ledger: None,
             }
         }
 
+        /// The ledger the new fragments are checked against: the ledger at
+        /// the tip with the fragments accepted since the tip was set applied.
+        ///
+        /// It is reset to the ledger of the tip when the tip changes, the
+        /// pending fragments are not applied again: the fragments depending
+        /// on a pending fragment not included in the new tip are rejected
+        /// until that fragment is included. The fragments removed from the
+        /// pool stay applied until the tip changes.
+        pub fn pending_ledger(&mut self, tip: &Ref) -> &mut Ledger {
+            let tip_hash = tip.hash();
+            let outdated = self
+                .pending_ledger
+                .as_ref()
+                .map_or(true, |(hash, _)| *hash != tip_hash);
+            if outdated {
+                self.pending_ledger = Some((tip_hash, Ledger::clone(tip.ledger())));
+            }
+            let (_, ledger) = self
+                .pending_ledger
+                .as_mut()
+                .expect("the pending ledger to be set");
+            ledger
+        }
+
+        pub fn contains(&self, fragment_id: &FragmentId) -> bool {
+            self.entries.contains_key(fragment_id)
+        }
+
+        /// number of pending fragments
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
         /// Returns clone of fragment if it was registered
+        ///
+        /// The limits of the pool are not enforced, see `insert_all`.
         pub fn insert(&mut self, fragment: Fragment) -> Option<Fragment> {
             let fragment_id = fragment.id();
-            let entry = match self.entries.entry(fragment_id) {
-                Entry::Occupied(_) => return None,
-                Entry::Vacant(vacant) => vacant,
-            };
+            if self.entries.contains_key(&fragment_id) {
+                return None;
+            }
             let pool_entry = Arc::new(PoolEntry::new(&fragment));
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            self.total_size += *pool_entry.fragment_size();
+            self.by_fee
+                .insert((pool_entry.fee_per_byte(), seq), fragment_id);
             let delay = self.expirations.insert(fragment_id, self.ttl);
-            entry.insert((pool_entry, fragment.clone(), delay));
-            self.entries_by_time.push_back(fragment_id);
+            self.entries
+                .insert(fragment_id, (pool_entry, fragment.clone(), delay, seq));
             Some(fragment)
         }
 
-        /// Returns clones of registered fragments
+        /// Returns clones of registered fragments and the IDs of the fragments
+        /// evicted to keep the pool within its limits.
+        ///
+        /// The registered fragments may be part of the evicted ones.
         pub fn insert_all(
             &mut self,
             fragments: impl IntoIterator<Item = Fragment>,
-        ) -> Vec<Fragment> {
-            fragments
+        ) -> (Vec<Fragment>, Vec<FragmentId>) {
+            let fragments = fragments
                 .into_iter()
                 .filter_map(|fragment| self.insert(fragment))
-                .collect()
+                .collect();
+            let evicted = self.evict_excess();
+            (fragments, evicted)
         }
 
         pub fn remove(&mut self, fragment_id: &FragmentId) -> Option<Fragment> {
-            if let Some((_, fragment, cache_key)) = self.entries.remove(fragment_id) {
-                self.entries_by_time
-                    .iter()
-                    .position(|id| id == fragment_id)
-                    .map(|position| {
-                        self.entries_by_time.remove(position);
-                    });
+            if let Some((pool_entry, fragment, cache_key, seq)) = self.entries.remove(fragment_id) {
                 self.expirations.remove(&cache_key);
+                self.forget(&pool_entry, seq);
                 Some(fragment)
             } else {
                 None
@@ -210,38 +439,329 @@ pub(super) mod internal {
         }
 
         pub fn remove_all(&mut self, fragment_ids: impl IntoIterator<Item = FragmentId>) {
-            // TODO fix terrible performance, entries_by_time are linear searched N times
             for fragment_id in fragment_ids {
                 self.remove(&fragment_id);
             }
         }
 
+        /// Iterate over the pending fragments and their entries, oldest first
+        pub fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a PoolEntry, &'a Fragment)> {
+            self.entries
+                .values()
+                .map(|(pool_entry, fragment, _, _)| (pool_entry.as_ref(), fragment))
+        }
+
         pub fn remove_oldest(&mut self) -> Option<Fragment> {
-            let fragment_id = self.entries_by_time.pop_front()?;
-            let (_, fragment, cache_key) = self
-                .entries
-                .remove(&fragment_id)
-                .expect("Pool lost fragment ID consistency");
+            let (_, (pool_entry, fragment, cache_key, seq)) = self.entries.pop_front()?;
             self.expirations.remove(&cache_key);
+            self.forget(&pool_entry, seq);
             Some(fragment)
         }
 
+        /// remove the accounting of an entry removed from the pending fragments
+        fn forget(&mut self, pool_entry: &PoolEntry, seq: u64) {
+            self.total_size -= *pool_entry.fragment_size();
+            self.by_fee.remove(&(pool_entry.fee_per_byte(), seq));
+        }
+
+        fn is_over_limits(&self) -> bool {
+            self.limits
+                .max_entries
+                .map_or(false, |max_entries| self.entries.len() > max_entries)
+                || self
+                    .limits
+                    .max_bytes
+                    .map_or(false, |max_bytes| self.total_size > max_bytes)
+        }
+
+        fn evict_excess(&mut self) -> Vec<FragmentId> {
+            let mut evicted = Vec::new();
+            while self.is_over_limits() {
+                let fragment_id = match self.limits.eviction_policy {
+                    EvictionPolicy::OldestFirst => {
+                        self.entries.front().map(|(fragment_id, _)| *fragment_id)
+                    }
+                    EvictionPolicy::LowestFee => self.lowest_fee(),
+                };
+                match fragment_id {
+                    Some(fragment_id) => {
+                        self.remove(&fragment_id);
+                        evicted.push(fragment_id);
+                    }
+                    None => break,
+                }
+            }
+            evicted
+        }
+
+        /// the pending fragment paying the lowest fee per byte, the oldest
+        /// one if several fragments pay the same fee per byte
+        fn lowest_fee(&self) -> Option<FragmentId> {
+            self.by_fee.values().next().cloned()
+        }
+
         pub fn poll_purge(&mut self) -> Poll<(), timer::Error> {
             loop {
                 match self.expirations.poll()? {
                     Async::NotReady => return Ok(Async::Ready(())),
                     Async::Ready(None) => return Ok(Async::Ready(())),
                     Async::Ready(Some(entry)) => {
-                        self.entries.remove(entry.get_ref());
-                        self.entries_by_time
-                            .iter()
-                            .position(|id| id == entry.get_ref())
-                            .map(|position| {
-                                self.entries_by_time.remove(position);
-                            });
+                        if let Some((pool_entry, _, _, seq)) = self.entries.remove(entry.get_ref())
+                        {
+                            self.forget(&pool_entry, seq);
+                        }
                     }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{internal, update_logs, PoolLimits};
+    use crate::{
+        blockcfg::Value,
+        fragment::{Fragment, FragmentId, Logs},
+        settings::start::config::EvictionPolicy,
+    };
+    use chain_addr::{Address, Discrimination, Kind};
+    use chain_core::property::Fragment as _;
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::transaction::{
+        AccountIdentifier, AuthenticatedTransaction, Input, InputEnum, NoExtra, Output, Transaction,
+    };
+    use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin};
+    use std::time::Duration;
+    use tokio::{prelude::future, runtime::current_thread::Runtime};
+
+    /// a transaction paying the given fee, all of them have the same size
+    fn fragment(fee: u64) -> Fragment {
+        let input_key = SecretKey::<Ed25519>::generate(&mut rand::thread_rng());
+        let output_key = SecretKey::<Ed25519>::generate(&mut rand::thread_rng());
+        let account = AccountIdentifier::from_single_account(input_key.to_public().into());
+        Fragment::Transaction(AuthenticatedTransaction {
+            transaction: Transaction {
+                inputs: vec![Input::from_enum(InputEnum::AccountInput(
+                    account,
+                    Value(100),
+                ))],
+                outputs: vec![Output {
+                    address: Address(Discrimination::Test, Kind::Single(output_key.to_public())),
+                    value: Value(100 - fee),
+                }],
+                extra: NoExtra,
+            },
+            witnesses: vec![],
+        })
+    }
+
+    fn limits(
+        max_entries: Option<usize>,
+        max_bytes: Option<usize>,
+        eviction_policy: EvictionPolicy,
+    ) -> PoolLimits {
+        PoolLimits {
+            max_entries,
+            max_bytes,
+            eviction_policy,
+        }
+    }
+
+    /// run the test with a pool, the expiration queue of the pool needs a timer
+    fn with_pool<F, T>(limits: PoolLimits, test: F) -> T
+    where
+        F: FnOnce(&mut internal::Pool) -> T,
+    {
+        let mut runtime = Runtime::new().unwrap();
+        runtime
+            .block_on(future::lazy(move || {
+                let mut pool = internal::Pool::new(Duration::from_secs(3600), limits);
+                Ok::<_, ()>(test(&mut pool))
+            }))
+            .unwrap()
+    }
+
+    #[test]
+    fn max_entries_evicts_the_oldest_fragments() {
+        let fragments = vec![fragment(5), fragment(1), fragment(3)];
+        let ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+
+        with_pool(limits(Some(2), None, EvictionPolicy::OldestFirst), |pool| {
+            let (inserted, evicted) = pool.insert_all(fragments);
+            assert_eq!(inserted.len(), 3);
+            assert_eq!(evicted, vec![ids[0]]);
+            assert_eq!(pool.len(), 2);
+            assert!(pool.contains(&ids[1]));
+            assert!(pool.contains(&ids[2]));
+        })
+    }
+
+    #[test]
+    fn max_entries_evicts_the_lowest_fee_fragments() {
+        let fragments = vec![fragment(5), fragment(1), fragment(3)];
+        let ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+        let cheapest = fragment(1);
+        let cheapest_id = cheapest.id();
+
+        with_pool(limits(Some(2), None, EvictionPolicy::LowestFee), |pool| {
+            let (_, evicted) = pool.insert_all(fragments);
+            assert_eq!(evicted, vec![ids[1]]);
+
+            // a new fragment paying less than the pending ones is evicted
+            // right away
+            let (inserted, evicted) = pool.insert_all(vec![cheapest]);
+            assert_eq!(inserted.len(), 1);
+            assert_eq!(evicted, vec![cheapest_id]);
+            assert!(pool.contains(&ids[0]));
+            assert!(pool.contains(&ids[2]));
+        })
+    }
+
+    #[test]
+    fn lowest_fee_eviction_prefers_the_oldest_on_equal_fees() {
+        let fragments = vec![fragment(2), fragment(2), fragment(2)];
+        let ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+
+        with_pool(limits(Some(2), None, EvictionPolicy::LowestFee), |pool| {
+            let (_, evicted) = pool.insert_all(fragments);
+            assert_eq!(evicted, vec![ids[0]]);
+        })
+    }
+
+    #[test]
+    fn removed_fragments_are_not_eviction_candidates() {
+        let fragments = vec![fragment(1), fragment(5), fragment(3)];
+        let ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+        let new_fragments = vec![fragment(4), fragment(6), fragment(2)];
+        let new_ids = new_fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+
+        with_pool(limits(Some(3), None, EvictionPolicy::LowestFee), |pool| {
+            pool.insert_all(fragments);
+            pool.remove(&ids[0]);
+            pool.remove_oldest();
+            let (_, evicted) = pool.insert_all(new_fragments);
+            assert_eq!(evicted, vec![new_ids[2]]);
+            assert!(pool.contains(&ids[2]));
+            assert_eq!(pool.len(), 3);
+        })
+    }
+
+    #[test]
+    fn max_bytes_evicts_fragments() {
+        let fragments = vec![fragment(1), fragment(2), fragment(3)];
+        let ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+        let size = fragments[0].to_raw().size_bytes_plus_size();
+
+        with_pool(
+            limits(None, Some(2 * size + 1), EvictionPolicy::LowestFee),
+            |pool| {
+                let (_, evicted) = pool.insert_all(fragments);
+                assert_eq!(evicted, vec![ids[0]]);
+                assert_eq!(pool.len(), 2);
+            },
+        )
+    }
+
+    #[test]
+    fn evicted_fragments_are_logged_as_evicted() {
+        let fragments = vec![fragment(5), fragment(1), fragment(3)];
+        let ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+        let mut logs = Logs::new(Duration::from_secs(3600));
+
+        let mut runtime = Runtime::new().unwrap();
+        let statuses = runtime
+            .block_on(future::lazy(move || {
+                let mut pool = internal::Pool::new(
+                    Duration::from_secs(3600),
+                    limits(Some(2), None, EvictionPolicy::LowestFee),
+                );
+                let (inserted, evicted) = pool.insert_all(fragments);
+                let new_logs = inserted
+                    .iter()
+                    .map(|fragment| FragmentLog::new(fragment.id().into(), FragmentOrigin::Rest))
+                    .collect();
+                let mut lookup = logs.clone();
+                update_logs(&mut logs, new_logs, evicted).and_then(move |()| {
+                    future::join_all(ids.into_iter().map(move |id: FragmentId| lookup.get(id)))
+                })
+            }))
+            .unwrap();
+
+        let statuses = statuses
+            .into_iter()
+            .map(|log| log.unwrap().status().clone())
+            .collect::<Vec<_>>();
+        assert!(statuses[0].is_pending());
+        assert!(statuses[1].is_evicted());
+        assert!(statuses[2].is_pending());
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use super::{internal, PoolLimits};
+    use crate::{
+        blockcfg::{ConfigParam, ConfigParams, Fragment},
+        settings::start::config::EvictionPolicy,
+    };
+    use chain_core::property::Fragment as _;
+    use std::time::Duration;
+    use test::Bencher;
+    use tokio::{prelude::future, runtime::current_thread::Runtime};
+
+    const PENDING_FRAGMENTS: u32 = 100_000;
+
+    fn fragments() -> Vec<Fragment> {
+        (0..PENDING_FRAGMENTS)
+            .map(|i| {
+                let mut params = ConfigParams::new();
+                params.push(ConfigParam::SlotsPerEpoch(i));
+                Fragment::Initial(params)
+            })
+            .collect()
+    }
+
+    fn pool() -> internal::Pool {
+        let limits = PoolLimits {
+            max_entries: None,
+            max_bytes: None,
+            eviction_policy: EvictionPolicy::LowestFee,
+        };
+        internal::Pool::new(Duration::from_secs(3600), limits)
+    }
+
+    #[bench]
+    fn insert_100k(b: &mut Bencher) {
+        let fragments = fragments();
+        // the expiration queue of the pool needs a timer
+        let mut runtime = Runtime::new().unwrap();
+        b.iter(|| {
+            runtime
+                .block_on(future::lazy(|| {
+                    let mut pool = pool();
+                    pool.insert_all(fragments.iter().cloned());
+                    Ok::<_, ()>(pool)
+                }))
+                .unwrap()
+        })
+    }
+
+    #[bench]
+    fn insert_and_remove_all_100k(b: &mut Bencher) {
+        let fragments = fragments();
+        // removed in reverse order, the worst case for a sequential lookup
+        let fragment_ids = fragments.iter().rev().map(|f| f.id()).collect::<Vec<_>>();
+        let mut runtime = Runtime::new().unwrap();
+        b.iter(|| {
+            runtime
+                .block_on(future::lazy(|| {
+                    let mut pool = pool();
+                    pool.insert_all(fragments.iter().cloned());
+                    pool.remove_all(fragment_ids.iter().cloned());
+                    Ok::<_, ()>(pool)
+                }))
+                .unwrap()
+        })
+    }
+}
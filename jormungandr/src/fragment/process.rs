@@ -1,12 +1,15 @@
 use crate::{
-    fragment::{Logs, Pool},
+    blockchain::Tip,
+    fragment::{Fragment, Logs, Pool, PoolLimits},
     intercom::{NetworkMsg, TransactionMsg},
+    network::p2p::topology::NodeId,
     stats_counter::StatsCounter,
     utils::{
         async_msg::{MessageBox, MessageQueue},
         task::TokioServiceInfo,
     },
 };
+use jormungandr_lib::interfaces::{FragmentOrigin, FragmentSubmission};
 use slog::Logger;
 use std::time::Duration;
 use tokio::{
@@ -26,13 +29,23 @@ pub struct Process {
 impl Process {
     pub fn new(
         pool_ttl: Duration,
+        pool_limits: PoolLimits,
         logs_ttl: Duration,
         garbage_collection_interval: Duration,
         network_msg_box: MessageBox<NetworkMsg>,
+        tip: Tip,
+        stats_counter: StatsCounter,
     ) -> Self {
         let logs = Logs::new(logs_ttl);
         Process {
-            pool: Pool::new(pool_ttl, logs.clone(), network_msg_box),
+            pool: Pool::new(
+                pool_ttl,
+                pool_limits,
+                logs.clone(),
+                network_msg_box,
+                tip,
+                stats_counter,
+            ),
             logs,
             garbage_collection_interval,
         }
@@ -55,23 +68,44 @@ impl Process {
         input.for_each(move |input| {
             match input {
                 TransactionMsg::SendTransaction(origin, txs) => {
-                    // Note that we cannot use apply_block here, since we don't have a valid context to which to apply
-                    // those blocks. one valid tx in a given context, could be invalid in another. for example
-                    // fee calculations, existence utxo / account solvency.
-
-                    // FIXME/TODO check that the txs are valid within themselves with basic requirements (e.g. inputs >= outputs).
-                    // we also want to keep a basic capability to filter away repetitive queries or definitely discarded txid.
+                    // The fragments are checked against the ledger at the current tip, which may
+                    // not be the context in which they end up being applied. A fragment valid now
+                    // may still be rejected at block creation, for example if its inputs are spent
+                    // in the meantime.
 
                     // This interface only makes sense for messages coming from arbitrary users (like transaction, certificates),
                     // for other message we don't want to receive them through this interface, and possibly
                     // put them in another pool.
 
+                    A(A(insert_and_count(
+                        self.pool.clone(),
+                        stats_counter.clone(),
+                        origin,
+                        None,
+                        txs,
+                        service_info.logger().clone(),
+                    )))
+                }
+                TransactionMsg::SendPeerTransaction(node_id, origin, txs) => {
+                    A(A(insert_and_count(
+                        self.pool.clone(),
+                        stats_counter.clone(),
+                        origin,
+                        Some(node_id),
+                        txs,
+                        service_info.logger().clone(),
+                    )))
+                }
+                TransactionMsg::SubmitTransaction(origin, txs, reply_handle) => {
                     let stats_counter = stats_counter.clone();
-                    A(self
+                    A(B(self
                         .pool
                         .clone()
-                        .insert_and_propagate_all(origin, txs, service_info.logger().clone())
-                        .map(move |count| stats_counter.add_tx_recv_cnt(count)))
+                        .insert_and_propagate_all(origin, None, txs, service_info.logger().clone())
+                        .map(move |submissions| {
+                            stats_counter.add_tx_recv_cnt(count_accepted(&submissions));
+                            reply_handle.reply_ok(submissions)
+                        })))
                 }
                 TransactionMsg::RemoveTransactions(fragment_ids, status) => B(self
                     .pool
@@ -95,3 +129,22 @@ impl Process {
             })
     }
 }
+
+fn insert_and_count(
+    mut pool: Pool,
+    stats_counter: StatsCounter,
+    origin: FragmentOrigin,
+    sender: Option<NodeId>,
+    txs: Vec<Fragment>,
+    logger: Logger,
+) -> impl Future<Item = (), Error = ()> {
+    pool.insert_and_propagate_all(origin, sender, txs, logger)
+        .map(move |submissions| stats_counter.add_tx_recv_cnt(count_accepted(&submissions)))
+}
+
+fn count_accepted(submissions: &[FragmentSubmission]) -> usize {
+    submissions
+        .iter()
+        .filter(|submission| submission.is_accepted())
+        .count()
+}
@@ -1,11 +1,18 @@
+use super::entry::FeePerByte;
 use super::logs::internal::Logs;
 use super::pool::internal::Pool;
 use crate::{
     blockcfg::{Contents, ContentsBuilder, HeaderContentEvalContext, Ledger, LedgerParameters},
-    fragment::FragmentId,
+    fragment::{Fragment, FragmentId},
 };
+use chain_addr::Address;
 use chain_core::property::Fragment as _;
+use chain_impl_mockchain::transaction::{AccountIdentifier, InputEnum, Transaction};
 use jormungandr_lib::interfaces::FragmentStatus;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
 
 pub enum SelectionOutput {
     Commit { fragment_id: FragmentId },
@@ -65,15 +72,12 @@ impl FragmentSelectionAlgorithm for OldestFirst {
                     total += 1;
                     ledger_simulation = ledger_new;
                 }
-                Err(error) => {
-                    use std::error::Error as _;
-                    let error = if let Some(source) = error.source() {
-                        format!("{}: {}", error, source)
-                    } else {
-                        error.to_string()
-                    };
-                    logs.modify(&id.into(), FragmentStatus::Rejected { reason: error })
-                }
+                Err(error) => logs.modify(
+                    &id.into(),
+                    FragmentStatus::Rejected {
+                        reason: rejection_reason(error),
+                    },
+                ),
             }
             if total >= self.max_per_block {
                 break;
@@ -81,3 +85,290 @@ impl FragmentSelectionAlgorithm for OldestFirst {
         }
     }
 }
+
+/// Select the pending fragments paying the highest fee per byte first.
+///
+/// A fragment spending the output of another pending fragment, or spending
+/// from an account an older pending fragment is already spending from, is
+/// only considered once the fragment it depends on has been processed.
+pub struct FeePriority {
+    builder: ContentsBuilder,
+    max_per_block: usize,
+}
+
+impl FeePriority {
+    pub fn new(max_per_block: usize) -> Self {
+        FeePriority {
+            builder: ContentsBuilder::new(),
+            max_per_block,
+        }
+    }
+}
+
+impl FragmentSelectionAlgorithm for FeePriority {
+    fn finalize(self) -> Contents {
+        self.builder.into()
+    }
+
+    fn select(
+        &mut self,
+        ledger: &Ledger,
+        ledger_params: &LedgerParameters,
+        metadata: &HeaderContentEvalContext,
+        logs: &mut Logs,
+        pool: &mut Pool,
+    ) {
+        let mut candidates = Candidate::collect(pool);
+        let mut ready = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| candidate.missing_dependencies == 0)
+            .map(|(index, candidate)| (candidate.fee_per_byte, Reverse(index)))
+            .collect::<BinaryHeap<_>>();
+        let mut total = 0usize;
+        let mut ledger_simulation = ledger.clone();
+
+        while total < self.max_per_block {
+            let index = match ready.pop() {
+                Some((_, Reverse(index))) => index,
+                None => break,
+            };
+            let id = candidates[index].id;
+            let fragment = pool.remove(&id).expect("Pool lost fragment ID consistency");
+            match ledger_simulation.apply_fragment(ledger_params, &fragment, metadata) {
+                Ok(ledger_new) => {
+                    self.builder.push(fragment);
+                    total += 1;
+                    ledger_simulation = ledger_new;
+                }
+                Err(error) => logs.modify(
+                    &id.into(),
+                    FragmentStatus::Rejected {
+                        reason: rejection_reason(error),
+                    },
+                ),
+            }
+
+            // the dependents of a rejected fragment are still released: they
+            // will be rejected in turn when applied to the simulated ledger
+            let dependents = std::mem::replace(&mut candidates[index].dependents, Vec::new());
+            for dependent in dependents {
+                let candidate = &mut candidates[dependent];
+                candidate.missing_dependencies -= 1;
+                if candidate.missing_dependencies == 0 {
+                    ready.push((candidate.fee_per_byte, Reverse(dependent)));
+                }
+            }
+        }
+    }
+}
+
+/// a pending fragment considered by the `FeePriority` selection
+struct Candidate {
+    id: FragmentId,
+    fee_per_byte: FeePerByte,
+    /// number of pending fragments to process before this one
+    missing_dependencies: usize,
+    /// indices of the candidates depending on this one
+    dependents: Vec<usize>,
+}
+
+impl Candidate {
+    /// collect the candidates of the pool, indexed by arrival order
+    fn collect(pool: &Pool) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = Vec::new();
+        let mut index_by_id = HashMap::new();
+        let mut last_spending_by_account: HashMap<AccountIdentifier, usize> = HashMap::new();
+
+        for (index, (pool_entry, fragment)) in pool.entries().enumerate() {
+            let id = *pool_entry.fragment_ref();
+
+            let mut dependencies = fragment_inputs(fragment)
+                .into_iter()
+                .filter_map(|input| match input {
+                    InputEnum::UtxoInput(pointer) => {
+                        index_by_id.get(&pointer.transaction_id).cloned()
+                    }
+                    InputEnum::AccountInput(account, _) => {
+                        last_spending_by_account.insert(account, index)
+                    }
+                })
+                // several inputs of the fragment may spend from the same
+                // account, the fragment does not depend on itself
+                .filter(|&dependency| dependency != index)
+                .collect::<Vec<usize>>();
+            dependencies.sort();
+            dependencies.dedup();
+            for &dependency in &dependencies {
+                candidates[dependency].dependents.push(index);
+            }
+
+            index_by_id.insert(id, index);
+            candidates.push(Candidate {
+                id,
+                fee_per_byte: pool_entry.fee_per_byte(),
+                missing_dependencies: dependencies.len(),
+                dependents: Vec::new(),
+            });
+        }
+
+        candidates
+    }
+}
+
+/// the inputs spent by the given fragment, if any
+fn fragment_inputs(fragment: &Fragment) -> Vec<InputEnum> {
+    match fragment {
+        Fragment::Transaction(auth_tx) => transaction_inputs(&auth_tx.transaction),
+        Fragment::OwnerStakeDelegation(auth_tx) => transaction_inputs(&auth_tx.transaction),
+        Fragment::StakeDelegation(auth_tx) => transaction_inputs(&auth_tx.transaction),
+        Fragment::PoolRegistration(auth_tx) => transaction_inputs(&auth_tx.transaction),
+        Fragment::PoolManagement(auth_tx) => transaction_inputs(&auth_tx.transaction),
+        _ => Vec::new(),
+    }
+}
+
+fn transaction_inputs<Extra>(transaction: &Transaction<Address, Extra>) -> Vec<InputEnum> {
+    transaction
+        .inputs
+        .iter()
+        .map(|input| input.to_enum())
+        .collect()
+}
+
+pub(super) fn rejection_reason<E: std::error::Error>(error: E) -> String {
+    if let Some(source) = error.source() {
+        format!("{}: {}", error, source)
+    } else {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Candidate, Pool};
+    use crate::{
+        blockcfg::Value,
+        fragment::{Fragment, PoolLimits},
+        settings::start::config::EvictionPolicy,
+    };
+    use chain_addr::{Address, Discrimination, Kind};
+    use chain_core::property::Fragment as _;
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::transaction::{
+        AccountIdentifier, AuthenticatedTransaction, Input, InputEnum, NoExtra, Output,
+        Transaction, UtxoPointer,
+    };
+    use std::time::Duration;
+    use tokio::{prelude::future, runtime::current_thread::Runtime};
+
+    fn account() -> AccountIdentifier {
+        let key = SecretKey::<Ed25519>::generate(&mut rand::thread_rng());
+        AccountIdentifier::from_single_account(key.to_public().into())
+    }
+
+    fn account_input(account: &AccountIdentifier, value: u64) -> InputEnum {
+        InputEnum::AccountInput(account.clone(), Value(value))
+    }
+
+    fn utxo_input(fragment: &Fragment, value: u64) -> InputEnum {
+        InputEnum::UtxoInput(UtxoPointer {
+            transaction_id: fragment.id(),
+            output_index: 0,
+            value: Value(value),
+        })
+    }
+
+    fn transaction(inputs: Vec<InputEnum>, output_value: u64) -> Fragment {
+        let key = SecretKey::<Ed25519>::generate(&mut rand::thread_rng());
+        Fragment::Transaction(AuthenticatedTransaction {
+            transaction: Transaction {
+                inputs: inputs.into_iter().map(Input::from_enum).collect(),
+                outputs: vec![Output {
+                    address: Address(Discrimination::Test, Kind::Single(key.to_public())),
+                    value: Value(output_value),
+                }],
+                extra: NoExtra,
+            },
+            witnesses: vec![],
+        })
+    }
+
+    fn candidates(fragments: Vec<Fragment>) -> Vec<Candidate> {
+        let limits = PoolLimits {
+            max_entries: None,
+            max_bytes: None,
+            eviction_policy: EvictionPolicy::OldestFirst,
+        };
+        // the expiration queue of the pool needs a timer
+        let mut runtime = Runtime::new().unwrap();
+        runtime
+            .block_on(future::lazy(move || {
+                let mut pool = Pool::new(Duration::from_secs(3600), limits);
+                pool.insert_all(fragments);
+                Ok::<_, ()>(Candidate::collect(&pool))
+            }))
+            .unwrap()
+    }
+
+    #[test]
+    fn spending_a_pending_output_depends_on_its_fragment() {
+        let funding = transaction(vec![account_input(&account(), 10)], 9);
+        let spending = transaction(vec![utxo_input(&funding, 9)], 8);
+        let unrelated = transaction(vec![account_input(&account(), 10)], 5);
+
+        let candidates = candidates(vec![funding, spending, unrelated]);
+
+        assert_eq!(candidates[0].missing_dependencies, 0);
+        assert_eq!(candidates[0].dependents, vec![1]);
+        assert_eq!(candidates[1].missing_dependencies, 1);
+        assert!(candidates[1].dependents.is_empty());
+        assert_eq!(candidates[2].missing_dependencies, 0);
+        assert!(candidates[2].dependents.is_empty());
+    }
+
+    #[test]
+    fn spending_from_an_account_depends_on_the_previous_spending() {
+        let account = account();
+        let first = transaction(vec![account_input(&account, 10)], 9);
+        let second = transaction(vec![account_input(&account, 10)], 8);
+        let third = transaction(vec![account_input(&account, 10)], 7);
+
+        let candidates = candidates(vec![first, second, third]);
+
+        assert_eq!(candidates[0].dependents, vec![1]);
+        assert_eq!(candidates[1].missing_dependencies, 1);
+        assert_eq!(candidates[1].dependents, vec![2]);
+        assert_eq!(candidates[2].missing_dependencies, 1);
+    }
+
+    #[test]
+    fn inputs_from_the_same_account_do_not_depend_on_the_fragment_itself() {
+        let account = account();
+        let first = transaction(
+            vec![account_input(&account, 5), account_input(&account, 5)],
+            9,
+        );
+        let second = transaction(
+            vec![account_input(&account, 3), account_input(&account, 3)],
+            5,
+        );
+
+        let candidates = candidates(vec![first, second]);
+
+        assert_eq!(candidates[0].missing_dependencies, 0);
+        assert_eq!(candidates[0].dependents, vec![1]);
+        assert_eq!(candidates[1].missing_dependencies, 1);
+        assert!(candidates[1].dependents.is_empty());
+    }
+
+    #[test]
+    fn fee_is_the_difference_between_inputs_and_outputs() {
+        let cheap = transaction(vec![account_input(&account(), 10)], 9);
+        let expensive = transaction(vec![account_input(&account(), 10)], 5);
+
+        let candidates = candidates(vec![cheap, expensive]);
+
+        assert!(candidates[1].fee_per_byte > candidates[0].fee_per_byte);
+    }
+}
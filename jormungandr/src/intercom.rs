@@ -1,10 +1,10 @@
 use crate::blockcfg::{Block, Epoch, Fragment, FragmentId, Header, HeaderHash};
-use crate::network::p2p::comm::PeerStats;
+use crate::network::p2p::comm::{Misbehavior, PeerStats, Reputation};
 use crate::network::p2p::topology::NodeId;
 use blockchain::Checkpoints;
 use futures::prelude::*;
 use futures::sync::{mpsc, oneshot};
-use jormungandr_lib::interfaces::{FragmentOrigin, FragmentStatus};
+use jormungandr_lib::interfaces::{FragmentOrigin, FragmentStatus, FragmentSubmission};
 use network_core::error as core_error;
 use slog::Logger;
 use std::{
@@ -71,6 +71,16 @@ impl Error {
         }
     }
 
+    pub fn unavailable<T>(cause: T) -> Self
+    where
+        T: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        Error {
+            code: core_error::Code::Unavailable,
+            cause: cause.into(),
+        }
+    }
+
     pub fn unimplemented<S: Into<String>>(message: S) -> Self {
         Error {
             code: core_error::Code::Unimplemented,
@@ -344,6 +354,18 @@ pub fn stream_request<T, E>(buffer: usize) -> (RequestStreamHandle<T>, RequestSi
 #[derive(Debug)]
 pub enum TransactionMsg {
     SendTransaction(FragmentOrigin, Vec<Fragment>),
+    /// same as `SendTransaction` for fragments received from the given
+    /// network peer, which is reported to the network task once per
+    /// batch if some fragments are invalid on their own; the fragments
+    /// rejected by the ledger do not count against the peer
+    SendPeerTransaction(NodeId, FragmentOrigin, Vec<Fragment>),
+    /// same as `SendTransaction`, replying with the outcome of the
+    /// submission of each fragment once the pool has checked them
+    SubmitTransaction(
+        FragmentOrigin,
+        Vec<Fragment>,
+        ReplyHandle<Vec<FragmentSubmission>>,
+    ),
     RemoveTransactions(Vec<FragmentId>, FragmentStatus),
 }
 
@@ -409,7 +431,9 @@ pub enum BlockMsg {
     /// The reply handle must be used to enable continued streaming by
     /// sending `Ok`, or to cancel the incoming stream with an error sent in
     /// `Err`.
-    NetworkBlock(Block, ReplyHandle<()>),
+    /// The ID of the peer the block was received from is given if known,
+    /// the peer is reported to the network task if the block is invalid.
+    NetworkBlock(Block, Option<NodeId>, ReplyHandle<()>),
     /// The stream of headers for missing chain blocks has been received
     /// from the network in response to a PullHeaders request or a Missing
     /// solicitation event.
@@ -435,6 +459,9 @@ pub enum NetworkMsg {
         to: HeaderHash,
     },
     PeerStats(ReplyHandle<Vec<(NodeId, PeerStats)>>),
+    /// A peer sent invalid data, lower its reputation
+    ReportMisbehavior(NodeId, Misbehavior),
+    PeerReputations(ReplyHandle<Vec<(NodeId, Reputation)>>),
 }
 
 /// Messages to the explorer task
@@ -72,6 +72,8 @@ use crate::{
     blockchain::Tip,
     fragment,
     intercom::BlockMsg,
+    settings::start::config::FragmentSelection,
+    stats_counter::StatsCounter,
     utils::{async_msg::MessageBox, task::TokioServiceInfo},
 };
 use chain_time::{
@@ -120,12 +122,15 @@ pub struct LeadershipModule {
     tip: Tip,
     block_message: MessageBox<BlockMsg>,
     garbage_collection_interval: Duration,
+    fragment_selection: FragmentSelection,
+    stats_counter: StatsCounter,
 }
 
 impl LeadershipModule {
     fn handle_schedule(&self, schedule: Schedule) {
         let logger = self.service_info.logger().new(o!("leader" => schedule.leader_event().id.to_string(), "date" => schedule.leader_event().date.to_string()));
         let fragment_pool = self.fragment_pool.clone();
+        let fragment_selection = self.fragment_selection;
         let tip = self.tip.clone();
         let enclave = self.enclave.clone();
         let leader_event: LeaderEvent = schedule.leader_event;
@@ -134,6 +139,7 @@ impl LeadershipModule {
         let sender = self.block_message.clone();
         let log_awake = schedule.log.mark_wake();
         let log_finish = schedule.log.mark_finished();
+        let stats_counter = self.stats_counter.clone();
 
         let tip_reference = tip
             .get_ref()
@@ -156,8 +162,14 @@ impl LeadershipModule {
                         nonce: None,
                     };
                     let next = (parent_id, chain_length, date);
-                    prepare_block(fragment_pool, eval_context, ledger, ledger_parameters)
-                        .join(future::ok(next))
+                    prepare_block(
+                        fragment_pool,
+                        fragment_selection,
+                        eval_context,
+                        ledger,
+                        ledger_parameters,
+                    )
+                    .join(future::ok(next))
                 })
                 .and_then(move |(contents, (parent_id, chain_length, date))| {
                     let ver = match leader_event.output {
@@ -213,6 +225,7 @@ impl LeadershipModule {
                         .send(BlockMsg::LeadershipBlock(block))
                         .map_err(|_send_error| ErrorKind::CannotSendLeadershipBlock.into())
                 })
+                .inspect(move |_| stats_counter.add_leader_block_cnt(1))
                 .and_then(|_: MessageBox<BlockMsg>| log_finish.map_err(|()| unreachable!()))
                 .map_err(|_: Error| unimplemented!()),
         );
@@ -285,6 +298,7 @@ leader elections please prevent your system from suspending or hibernating.
         let epoch = leadership.epoch();
         let time_frame = new_epoch_event.time_frame;
         let logs = self.logs.clone();
+        let stats_counter = self.stats_counter.clone();
 
         let current_slot = time_frame.slot_at(&std::time::SystemTime::now()).unwrap();
         let within_era = era.from_slot_to_era(current_slot).unwrap();
@@ -340,6 +354,7 @@ leader elections please prevent your system from suspending or hibernating.
                                 "block date" => schedule.date.to_string(),
                                 "scheduled_at" => slot_system_time.to_string(),
                             );
+                            stats_counter.add_leader_event_cnt(1);
 
                             future::Either::B(
                                 scheduler
@@ -381,11 +396,13 @@ leader elections please prevent your system from suspending or hibernating.
         service_info: TokioServiceInfo,
         logs: Logs,
         garbage_collection_interval: Duration,
+        fragment_selection: FragmentSelection,
         enclave: Enclave,
         fragment_pool: fragment::Pool,
         tip_branch: Tip,
         new_epoch_events: mpsc::Receiver<NewEpochToSchedule>,
         block_message: MessageBox<BlockMsg>,
+        stats_counter: StatsCounter,
     ) -> impl Future<Item = (), Error = Error> {
         let scheduler_future = Schedules::new().into_future();
         let new_epoch_future = new_epoch_events.into_future();
@@ -398,6 +415,8 @@ leader elections please prevent your system from suspending or hibernating.
             tip: tip_branch,
             block_message,
             garbage_collection_interval,
+            fragment_selection,
+            stats_counter,
         };
 
         leadership_module
@@ -472,20 +491,38 @@ leader elections please prevent your system from suspending or hibernating.
 
 fn prepare_block(
     mut fragment_pool: fragment::Pool,
+    fragment_selection: FragmentSelection,
     eval_context: HeaderContentEvalContext,
     ledger: &Arc<Ledger>,
     epoch_parameters: Arc<LedgerParameters>,
 ) -> impl Future<Item = Contents, Error = Error> {
-    use crate::fragment::selection::{FragmentSelectionAlgorithm as _, OldestFirst};
-
-    let selection_algorithm = OldestFirst::new(250 /* TODO!! */);
-    fragment_pool
-        .select(
-            ledger.as_ref().clone(),
-            eval_context,
-            epoch_parameters.as_ref().clone(),
-            selection_algorithm,
-        )
-        .map(|selection_algorithm| selection_algorithm.finalize())
-        .map_err(|()| ErrorKind::FragmentSelectionFailed.into())
+    use crate::fragment::selection::{FeePriority, FragmentSelectionAlgorithm as _, OldestFirst};
+
+    const MAX_PER_BLOCK: usize = 250; // TODO!!
+
+    let ledger = ledger.as_ref().clone();
+    let epoch_parameters = epoch_parameters.as_ref().clone();
+    match fragment_selection {
+        FragmentSelection::OldestFirst => future::Either::A(
+            fragment_pool
+                .select(
+                    ledger,
+                    eval_context,
+                    epoch_parameters,
+                    OldestFirst::new(MAX_PER_BLOCK),
+                )
+                .map(|selection_algorithm| selection_algorithm.finalize()),
+        ),
+        FragmentSelection::FeePriority => future::Either::B(
+            fragment_pool
+                .select(
+                    ledger,
+                    eval_context,
+                    epoch_parameters,
+                    FeePriority::new(MAX_PER_BLOCK),
+                )
+                .map(|selection_algorithm| selection_algorithm.finalize()),
+        ),
+    }
+    .map_err(|()| ErrorKind::FragmentSelectionFailed.into())
 }
@@ -1,3 +1,5 @@
+#![cfg_attr(feature = "with-bench", feature(test))]
+
 extern crate actix_net;
 extern crate actix_threadpool;
 extern crate actix_web;
@@ -49,6 +51,8 @@ extern crate slog_json;
 extern crate slog_syslog;
 extern crate slog_term;
 extern crate structopt;
+#[cfg(all(test, feature = "with-bench"))]
+extern crate test;
 extern crate thiserror;
 extern crate tk_listen;
 extern crate tokio;
@@ -62,7 +66,7 @@ use crate::{
     utils::{async_msg, task::Services},
 };
 use futures::Future;
-use settings::{start::RawSettings, CommandLine};
+use settings::{start::RawSettings, Command, CommandLine, StorageCommand};
 use slog::Logger;
 use std::thread;
 use std::time::Duration;
@@ -107,6 +111,8 @@ pub struct BootstrappedNode {
 }
 
 const FRAGMENT_TASK_QUEUE_LEN: usize = 1024;
+// TODO: we should get this value from the configuration
+const BLOCK_CACHE_TTL: Duration = Duration::from_secs(5 * 24 * 3600);
 const NETWORK_TASK_QUEUE_LEN: usize = 32;
 
 fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::Error> {
@@ -127,20 +133,25 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         leadership::Logs::new(bootstrapped_node.settings.leadership.log_ttl.into());
     let leadership_garbage_collection_interval =
         bootstrapped_node.settings.leadership.log_ttl.into();
+    let leadership_fragment_selection = bootstrapped_node.settings.leadership.fragment_selection;
 
     let stats_counter = StatsCounter::default();
 
     let (fragment_pool, pool_logs) = {
         let stats_counter = stats_counter.clone();
+        let mempool = &bootstrapped_node.settings.mempool;
         let process = fragment::Process::new(
-            bootstrapped_node.settings.mempool.fragment_ttl.into(),
-            bootstrapped_node.settings.mempool.log_ttl.into(),
-            bootstrapped_node
-                .settings
-                .mempool
-                .garbage_collection_interval
-                .into(),
+            mempool.fragment_ttl.into(),
+            fragment::PoolLimits {
+                max_entries: mempool.max_entries,
+                max_bytes: mempool.max_bytes,
+                eviction_policy: mempool.eviction_policy,
+            },
+            mempool.log_ttl.into(),
+            mempool.garbage_collection_interval.into(),
             network_msgbox.clone(),
+            blockchain_tip.clone(),
+            stats_counter.clone(),
         );
 
         let pool = process.pool().clone();
@@ -253,17 +264,20 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         let block_task = block_task.clone();
         let blockchain_tip = blockchain_tip.clone();
         let enclave = leadership::Enclave::new(enclave.clone());
+        let stats_counter = stats_counter.clone();
 
         services.spawn_future("leadership", move |info| {
             leadership::LeadershipModule::start(
                 info,
                 leadership_logs,
                 leadership_garbage_collection_interval,
+                leadership_fragment_selection,
                 enclave,
                 fragment_pool,
                 blockchain_tip,
                 new_epoch_notifier,
                 block_task,
+                stats_counter,
             )
             .map_err(|e| unimplemented!("error in leadership {}", e))
         });
@@ -336,27 +350,36 @@ fn bootstrap(initialized_node: InitializedNode) -> Result<BootstrappedNode, star
 
     let block0_explorer = block0.clone();
 
-    // TODO: we should get this value from the configuration
-    let block_cache_ttl: Duration = Duration::from_secs(5 * 24 * 3600);
-
     let (blockchain, blockchain_tip) = start_up::load_blockchain(
         block0,
         storage,
         new_epoch_announcements.clone(),
-        block_cache_ttl,
+        BLOCK_CACHE_TTL,
+        settings.served_blocks,
     )?;
 
+    let bootstrap_progress = rest_context
+        .as_ref()
+        .map(|context| context.bootstrap_progress().clone())
+        .unwrap_or_default();
+
     let bootstrapped = network::bootstrap(
         &settings.network,
         blockchain.clone(),
         blockchain_tip.clone(),
+        bootstrap_progress,
         &bootstrap_logger,
     )?;
 
     let explorer_db = if settings.explorer {
+        let explorer_store = match &settings.storage {
+            Some(dir) => Some(explorer::ExplorerStore::open(dir.join("explorer.sled"))?),
+            None => None,
+        };
         Some(explorer::ExplorerDB::bootstrap(
             block0_explorer,
             &blockchain,
+            explorer_store,
         )?)
     } else {
         None
@@ -391,7 +414,7 @@ pub struct InitializedNode {
 }
 
 fn initialize_node() -> Result<InitializedNode, start_up::Error> {
-    let command_line = CommandLine::load();
+    let mut command_line = CommandLine::load();
 
     if command_line.full_version {
         println!("{}", env!("FULL_VERSION"));
@@ -401,6 +424,7 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
         std::process::exit(0);
     }
 
+    let command = command_line.command.take();
     let raw_settings = RawSettings::load(command_line)?;
 
     let logger = raw_settings.to_logger()?;
@@ -414,6 +438,11 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
     info!(init_logger, "Starting {}", env!("FULL_VERSION"),);
     let settings = raw_settings.try_into_settings(&init_logger)?;
 
+    if let Some(Command::Storage(command)) = command {
+        run_storage_command(&settings, command, &init_logger)?;
+        std::process::exit(0);
+    }
+
     let rest_context = match &settings.rest {
         Some(rest) => {
             let context = rest::Context::new();
@@ -448,6 +477,39 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
     })
 }
 
+fn run_storage_command(
+    settings: &Settings,
+    command: StorageCommand,
+    logger: &Logger,
+) -> Result<(), start_up::Error> {
+    let storage = start_up::prepare_storage(settings, logger)?;
+    let block0 =
+        |storage: &start_up::NodeStorage| start_up::prepare_block_0(settings, storage, logger);
+
+    match command {
+        StorageCommand::Verify => {
+            start_up::check_storage(block0(&storage)?, storage, BLOCK_CACHE_TTL, false, logger)
+        }
+        StorageCommand::Repair => {
+            start_up::check_storage(block0(&storage)?, storage, BLOCK_CACHE_TTL, true, logger)
+        }
+        StorageCommand::Export { from, to, output } => {
+            start_up::export_blocks(&block0(&storage)?, storage, from, to, &output, logger)
+        }
+        StorageCommand::Import { input } => start_up::import_blocks(
+            block0(&storage)?,
+            storage,
+            BLOCK_CACHE_TTL,
+            settings.served_blocks,
+            &input,
+            logger,
+        ),
+        StorageCommand::Migrate { from } => {
+            start_up::migrate_storage(settings, storage, &from, logger)
+        }
+    }
+}
+
 fn main() {
     use std::error::Error;
 
@@ -1,32 +1,50 @@
-use super::{grpc, BlockConfig};
-use crate::blockcfg::{Block, HeaderHash};
-use crate::blockchain::{self, Blockchain, Error as BlockchainError, PreCheckedHeader, Ref, Tip};
+use super::{chunk_sizes, grpc, BlockConfig};
+use crate::blockcfg::{Block, ChainLength, Header, HeaderHash};
+use crate::blockchain::{
+    self, Blockchain, Error as BlockchainError, PreCheckedHeader, Ref, Tip, MAIN_BRANCH_TAG,
+};
 use crate::settings::start::network::Peer;
 use chain_core::property::HasHeader;
+use futures::sync::{mpsc, oneshot};
 use network_core::client::{BlockService, Client as _};
 use network_core::error::Error as NetworkError;
 use network_grpc::client::Connection;
 use slog::Logger;
 use thiserror::Error;
+use tokio::executor::DefaultExecutor;
 use tokio::prelude::*;
-use tokio::runtime::Runtime;
+use tokio::runtime::{Runtime, TaskExecutor};
+use tokio::sync::lock::Lock;
 
-use std::fmt::Debug;
 use std::io;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// interval between two logs of the bootstrap progress
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("runtime initialization failed")]
     RuntimeInit { source: io::Error },
-    #[error("failed to connect to bootstrap peer")]
-    Connect { source: grpc::ConnectError },
+    #[error("none of the trusted peers could be reached")]
+    NoPeerAvailable,
     #[error("connection broken")]
     ClientNotReady { source: NetworkError },
     #[error("bootstrap pull request failed")]
     PullRequestFailed { source: NetworkError },
     #[error("bootstrap pull stream failed")]
     PullStreamFailed { source: NetworkError },
+    #[error("received header {0} does not follow the previous one")]
+    InvalidHeaderChain(HeaderHash),
+    #[error("received headers do not reach the peer's tip {0}")]
+    IncompleteHeaderChain(HeaderHash),
+    #[error("received fewer blocks than requested")]
+    IncompleteRange,
+    #[error("received block does not match the requested header {0}")]
+    UnexpectedBlock(HeaderHash),
+    #[error("failed to read the blocks to import")]
+    ReadBlocksFailed { source: io::Error },
     #[error("block header check failed")]
     HeaderCheckFailed { source: BlockchainError },
     #[error("received block {0} is already present")]
@@ -35,71 +53,507 @@ pub enum Error {
     BlockMissingParent(HeaderHash),
     #[error("failed to apply block to the blockchain")]
     ApplyBlockFailed { source: BlockchainError },
+    #[error("failed to store blocks")]
+    StoreBlocksFailed { source: BlockchainError },
+    #[error("the task storing the blocks has stopped")]
+    StoreBlocksStopped,
     #[error("failed to select the new tip")]
     ChainSelectionFailed { source: blockchain::ProcessError },
 }
 
-pub fn bootstrap_from_peer(
-    peer: Peer,
+/// Progress of the bootstrap, shared with the REST API.
+#[derive(Clone, Default)]
+pub struct Progress {
+    inner: Arc<RwLock<Option<ProgressState>>>,
+}
+
+struct ProgressState {
+    started: Instant,
+    start_chain_length: u32,
+    chain_length: u32,
+    target_chain_length: u32,
+    last_log: Instant,
+}
+
+/// Snapshot of the bootstrap progress.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressReport {
+    pub chain_length: u32,
+    pub target_chain_length: u32,
+    pub blocks_per_second: f64,
+    /// estimated number of seconds until the target chain length is
+    /// reached, unknown until some blocks have been stored
+    pub eta: Option<u64>,
+}
+
+impl Progress {
+    /// Starts tracking the bootstrap from the given chain length, with
+    /// `blocks` blocks to download.
+    fn start(&self, chain_length: ChainLength, blocks: usize) {
+        let now = Instant::now();
+        let chain_length = u32::from(chain_length);
+        *self.inner.write().expect("bootstrap progress poisoned") = Some(ProgressState {
+            started: now,
+            start_chain_length: chain_length,
+            chain_length,
+            target_chain_length: chain_length + blocks as u32,
+            last_log: now,
+        });
+    }
+
+    /// Records that the blocks up to the given chain length are stored,
+    /// logging the progress from time to time.
+    fn update(&self, chain_length: ChainLength, logger: &Logger) {
+        let mut inner = self.inner.write().expect("bootstrap progress poisoned");
+        if let Some(state) = inner.as_mut() {
+            state.chain_length = u32::from(chain_length);
+            if state.last_log.elapsed() >= PROGRESS_LOG_INTERVAL {
+                state.last_log = Instant::now();
+                let report = state.report();
+                info!(logger, "bootstrapping";
+                    "chain_length" => report.chain_length,
+                    "target_chain_length" => report.target_chain_length,
+                    "blocks_per_second" => format!("{:.1}", report.blocks_per_second),
+                    "eta_seconds" => ?report.eta,
+                );
+            }
+        }
+    }
+
+    /// Returns the current progress, or `None` if the bootstrap has not
+    /// started downloading blocks.
+    pub fn report(&self) -> Option<ProgressReport> {
+        self.inner
+            .read()
+            .expect("bootstrap progress poisoned")
+            .as_ref()
+            .map(ProgressState::report)
+    }
+}
+
+impl ProgressState {
+    fn report(&self) -> ProgressReport {
+        let stored = self.chain_length - self.start_chain_length;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let blocks_per_second = if elapsed > 0.0 {
+            f64::from(stored) / elapsed
+        } else {
+            0.0
+        };
+        let remaining = self.target_chain_length.saturating_sub(self.chain_length);
+        let eta = if blocks_per_second > 0.0 {
+            Some((f64::from(remaining) / blocks_per_second).ceil() as u64)
+        } else {
+            None
+        };
+        ProgressReport {
+            chain_length: self.chain_length,
+            target_chain_length: self.target_chain_length,
+            blocks_per_second,
+            eta,
+        }
+    }
+}
+
+/// Bootstraps from the given trusted peers: the header chain from the
+/// current tip to the tip of one of the peers is pulled first, then the
+/// blocks are downloaded by ranges, in parallel from all the reachable
+/// peers. A range that cannot be downloaded from a peer, or that does not
+/// match the header chain, is requested from the other peers.
+pub fn bootstrap_from_peers(
+    peers: Vec<Peer>,
     blockchain: Blockchain,
     branch: Tip,
+    progress: Progress,
     logger: Logger,
 ) -> Result<Arc<Ref>, Error> {
-    info!(logger, "connecting to bootstrap peer {}", peer.connection);
-
     let runtime = Runtime::new().map_err(|e| Error::RuntimeInit { source: e })?;
 
     let blockchain2 = blockchain.clone();
     let logger2 = logger.clone();
 
-    let bootstrap = grpc::connect(peer.address(), None, runtime.executor())
-        .map_err(|e| Error::Connect { source: e })
-        .and_then(|client: Connection<BlockConfig>| {
-            client
-                .ready()
-                .map_err(|e| Error::ClientNotReady { source: e })
-        })
+    let bootstrap = connect_peers(peers, runtime.executor(), logger.clone())
         .join(branch.get_ref().map_err(|_| unreachable!()))
-        .and_then(move |(mut client, tip)| {
-            let tip_hash = tip.hash();
-            debug!(logger, "pulling blocks starting from {}", tip_hash);
-            client
-                .pull_blocks_to_tip(&[tip_hash])
-                .map_err(|e| Error::PullRequestFailed { source: e })
-                .and_then(move |stream| bootstrap_from_stream(blockchain, tip, stream, logger))
+        .and_then(move |(peers, tip)| {
+            let fetch_logger = logger.clone();
+            pull_header_chain(peers.clone(), tip.clone(), logger.clone()).and_then(
+                move |block_ids| {
+                    info!(logger, "downloading {} blocks", block_ids.len());
+                    progress.start(tip.chain_length(), block_ids.len());
+                    let stream = fetch_blocks(peers, block_ids, fetch_logger);
+                    bootstrap_from_stream(blockchain, tip, stream, progress, logger)
+                },
+            )
         })
-        .and_then(move |tip| {
-            blockchain::process_new_ref(logger2, blockchain2, branch, tip.clone())
-                .map_err(|e| Error::ChainSelectionFailed { source: e })
-                .map(|()| tip)
-        });
+        .and_then(move |tip| select_tip(blockchain2, branch, tip, logger2));
 
     runtime.block_on_all(bootstrap)
 }
 
+/// A connected bootstrap peer, shared by the concurrent requests.
+#[derive(Clone)]
+struct PeerClient {
+    connection: Lock<Connection<BlockConfig>>,
+    logger: Logger,
+}
+
+impl PeerClient {
+    /// Waits for the connection to be ready and starts the request.
+    fn request<F, R>(&self, request: F) -> impl Future<Item = R, Error = Error>
+    where
+        F: FnOnce(&mut Connection<BlockConfig>) -> R,
+    {
+        let mut connection = self.connection.clone();
+        let mut request = Some(request);
+        future::poll_fn(move || {
+            let mut guard = try_ready!(Ok(connection.poll_lock()));
+            try_ready!(guard
+                .poll_ready()
+                .map_err(|e| Error::ClientNotReady { source: e }));
+            let request = request.take().expect("request polled after completion");
+            Ok(Async::Ready(request(&mut *guard)))
+        })
+    }
+
+    fn get_blocks(
+        &self,
+        block_ids: Vec<HeaderHash>,
+    ) -> impl Future<Item = Vec<Block>, Error = Error> {
+        self.request(move |connection| connection.get_blocks(&block_ids))
+            .and_then(|future| future.map_err(|e| Error::PullRequestFailed { source: e }))
+            .and_then(|stream| {
+                stream
+                    .map_err(|e| Error::PullStreamFailed { source: e })
+                    .collect()
+            })
+    }
+}
+
+fn connect_peers(
+    peers: Vec<Peer>,
+    executor: TaskExecutor,
+    logger: Logger,
+) -> impl Future<Item = Vec<PeerClient>, Error = Error> {
+    let connections = peers.into_iter().map(move |peer| {
+        let logger = logger.new(o!("peer_addr" => peer.connection.to_string()));
+        info!(logger, "connecting to bootstrap peer");
+        grpc::connect(peer.address(), None, executor.clone()).then(move |res| match res {
+            Ok(connection) => Ok(Some(PeerClient {
+                connection: Lock::new(connection),
+                logger,
+            })),
+            Err(e) => {
+                warn!(logger, "unable to reach peer for initial bootstrap"; "reason" => %e);
+                Ok(None)
+            }
+        })
+    });
+    future::join_all(connections).and_then(|peers| {
+        let peers = peers.into_iter().flatten().collect::<Vec<_>>();
+        if peers.is_empty() {
+            Err(Error::NoPeerAvailable)
+        } else {
+            Ok(peers)
+        }
+    })
+}
+
+/// Pulls the headers from the tip to the tip of the first peer able to
+/// send a chain of headers following each other, and returns their hashes.
+fn pull_header_chain(
+    peers: Vec<PeerClient>,
+    tip: Arc<Ref>,
+    logger: Logger,
+) -> impl Future<Item = Vec<HeaderHash>, Error = Error> {
+    future::loop_fn(0, move |index| {
+        let peer = peers[index].clone();
+        let attempts = peers.len();
+        let logger = logger.clone();
+        pull_headers_from(peer.clone(), tip.clone()).then(move |res| match res {
+            Ok(block_ids) => Ok(future::Loop::Break(block_ids)),
+            Err(e) => {
+                warn!(peer.logger, "failed to pull the headers"; "error" => ?e);
+                if index + 1 < attempts {
+                    Ok(future::Loop::Continue(index + 1))
+                } else {
+                    error!(logger, "no peer could send the headers");
+                    Err(e)
+                }
+            }
+        })
+    })
+}
+
+/// Pulls the headers from the tip to the tip of the peer, checking that
+/// each header follows the previous one.
+fn pull_headers_from(
+    peer: PeerClient,
+    tip: Arc<Ref>,
+) -> impl Future<Item = Vec<HeaderHash>, Error = Error> {
+    let tip_hash = tip.hash();
+    let tip_chain_length = tip.chain_length();
+    let peer2 = peer.clone();
+    peer.request(|connection| connection.tip())
+        .and_then(|future| future.map_err(|e| Error::PullRequestFailed { source: e }))
+        .and_then(move |peer_tip| {
+            let peer_tip_hash = peer_tip.hash();
+            debug!(
+                peer2.logger,
+                "pulling headers from {} to {}", tip_hash, peer_tip_hash
+            );
+            pull_headers_until(tip_hash, tip_chain_length, peer_tip_hash, move |from| {
+                peer2
+                    .request(move |connection| connection.pull_headers(&[from], &peer_tip_hash))
+                    .and_then(|future| future.map_err(|e| Error::PullRequestFailed { source: e }))
+                    .map(|stream| stream.map_err(|e| Error::PullStreamFailed { source: e }))
+            })
+        })
+}
+
+/// Pulls the headers following `tip_hash` with `pull`, each pull starting
+/// from the last header received, until `peer_tip_hash` is reached. The
+/// peers send a limited number of headers per request, so long chains take
+/// several pulls.
+fn pull_headers_until<F, R, S>(
+    tip_hash: HeaderHash,
+    tip_chain_length: ChainLength,
+    peer_tip_hash: HeaderHash,
+    pull: F,
+) -> impl Future<Item = Vec<HeaderHash>, Error = Error>
+where
+    F: FnMut(HeaderHash) -> R,
+    R: Future<Item = S, Error = Error>,
+    S: Stream<Item = Header, Error = Error>,
+{
+    future::loop_fn(
+        (tip_hash, tip_chain_length, Vec::new(), pull),
+        move |(from, chain_length, block_ids, mut pull)| {
+            pull(from)
+                .and_then(move |stream| {
+                    stream.fold(
+                        (from, chain_length, block_ids),
+                        |(parent_hash, chain_length, mut block_ids), header| {
+                            use chain_core::property::ChainLength as _;
+
+                            let block_id = header.hash();
+                            if *header.block_parent_hash() != parent_hash
+                                || header.chain_length() != chain_length.next()
+                            {
+                                return Err(Error::InvalidHeaderChain(block_id));
+                            }
+                            block_ids.push(block_id);
+                            Ok((block_id, header.chain_length(), block_ids))
+                        },
+                    )
+                })
+                .and_then(move |(last_hash, chain_length, block_ids)| {
+                    if last_hash == peer_tip_hash {
+                        Ok(future::Loop::Break(block_ids))
+                    } else if last_hash == from {
+                        // no progress: the peer does not have its tip's chain
+                        Err(Error::IncompleteHeaderChain(peer_tip_hash))
+                    } else {
+                        Ok(future::Loop::Continue((
+                            last_hash,
+                            chain_length,
+                            block_ids,
+                            pull,
+                        )))
+                    }
+                })
+        },
+    )
+}
+
+/// Downloads the blocks by ranges, spread over the peers, and streams them
+/// in order.
+fn fetch_blocks(
+    peers: Vec<PeerClient>,
+    block_ids: Vec<HeaderHash>,
+    logger: Logger,
+) -> impl Stream<Item = Block, Error = Error> {
+    let ranges = block_ids
+        .chunks(chunk_sizes::CHAIN_PULL)
+        .map(<[_]>::to_vec)
+        .collect::<Vec<_>>();
+    let parallel_requests = peers.len();
+    stream::iter_ok(ranges.into_iter().enumerate())
+        .map(move |(index, range)| {
+            fetch_range(peers.clone(), index % peers.len(), range, logger.clone())
+        })
+        .buffered(parallel_requests)
+        .map(stream::iter_ok::<_, Error>)
+        .flatten()
+}
+
+/// Downloads a range of blocks, starting with the peer `first`, then
+/// trying the other peers in turn if it fails.
+fn fetch_range(
+    peers: Vec<PeerClient>,
+    first: usize,
+    block_ids: Vec<HeaderHash>,
+    logger: Logger,
+) -> impl Future<Item = Vec<Block>, Error = Error> {
+    future::loop_fn(0, move |attempt| {
+        let peer = peers[(first + attempt) % peers.len()].clone();
+        let attempts = peers.len();
+        let block_ids = block_ids.clone();
+        let logger = logger.clone();
+        peer.get_blocks(block_ids.clone())
+            .and_then(move |blocks| check_range(&block_ids, blocks))
+            .then(move |res| match res {
+                Ok(blocks) => Ok(future::Loop::Break(blocks)),
+                Err(e) => {
+                    warn!(peer.logger, "failed to download a range of blocks"; "error" => ?e);
+                    if attempt + 1 < attempts {
+                        Ok(future::Loop::Continue(attempt + 1))
+                    } else {
+                        error!(logger, "no peer could send a range of blocks");
+                        Err(e)
+                    }
+                }
+            })
+    })
+}
+
+/// Checks the blocks are the ones of the header chain that were requested.
+fn check_range(block_ids: &[HeaderHash], blocks: Vec<Block>) -> Result<Vec<Block>, Error> {
+    if blocks.len() != block_ids.len() {
+        return Err(Error::IncompleteRange);
+    }
+    for (block_id, block) in block_ids.iter().zip(&blocks) {
+        let (content_hash, _) = block.contents.compute_hash_size();
+        if block.header.hash() != *block_id || block.header.block_content_hash() != content_hash {
+            return Err(Error::UnexpectedBlock(*block_id));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Validates and stores the blocks read from a file, in order, each block
+/// having to be the child of the previous one (or of the current tip for
+/// the first one).
+pub fn import_blocks<I>(
+    blocks: I,
+    blockchain: Blockchain,
+    branch: Tip,
+    logger: Logger,
+) -> Result<Arc<Ref>, Error>
+where
+    I: IntoIterator<Item = io::Result<Block>>,
+    I::IntoIter: Send + 'static,
+{
+    let runtime = Runtime::new().map_err(|e| Error::RuntimeInit { source: e })?;
+
+    let blockchain2 = blockchain.clone();
+    let logger2 = logger.clone();
+    let blocks = blocks.into_iter();
+
+    let import = branch
+        .get_ref()
+        .map_err(|_| unreachable!())
+        .and_then(move |tip| {
+            let stream =
+                stream::iter_result(blocks).map_err(|e| Error::ReadBlocksFailed { source: e });
+            bootstrap_from_stream(blockchain, tip, stream, Progress::default(), logger)
+        })
+        .and_then(move |tip| select_tip(blockchain2, branch, tip, logger2));
+
+    runtime.block_on_all(import)
+}
+
+fn select_tip(
+    blockchain: Blockchain,
+    branch: Tip,
+    tip: Arc<Ref>,
+    logger: Logger,
+) -> impl Future<Item = Arc<Ref>, Error = Error> {
+    blockchain::process_new_ref(logger, blockchain, branch, tip.clone())
+        .map_err(|e| Error::ChainSelectionFailed { source: e })
+        .map(|()| tip)
+}
+
+/// Applies the blocks by chunks: while a chunk is written to the storage
+/// by a separate task, the ledger is applied on the blocks of the next one.
+///
+/// The `HEAD` tag is moved to the last block of each chunk along with its
+/// storage, so an interrupted bootstrap resumes from there on the next
+/// start.
 fn bootstrap_from_stream<S>(
     blockchain: Blockchain,
     tip: Arc<Ref>,
     stream: S,
+    progress: Progress,
     logger: Logger,
 ) -> impl Future<Item = Arc<Ref>, Error = Error>
 where
-    S: Stream<Item = Block, Error = NetworkError>,
-    S::Error: Debug,
+    S: Stream<Item = Block, Error = Error>,
 {
+    let (write_queue, chunks_to_write) = mpsc::channel::<Vec<Block>>(1);
+    let mut writer_blockchain = blockchain.clone();
+    let writer_logger = logger.clone();
+    let writer = chunks_to_write
+        .map_err(|_| unreachable!())
+        .for_each(move |blocks| {
+            let last_header = blocks
+                .last()
+                .expect("chunks of blocks are not empty")
+                .header
+                .clone();
+            let progress = progress.clone();
+            let logger = writer_logger.clone();
+            writer_blockchain
+                .store_blocks(blocks, MAIN_BRANCH_TAG)
+                .map(move |()| progress.update(last_header.chain_length(), &logger))
+                .map_err(|e| Error::StoreBlocksFailed { source: e })
+        });
+    let writer = oneshot::spawn(writer, &DefaultExecutor::current());
+
     let fold_logger = logger.clone();
-    stream
-        .map_err(|e| Error::PullStreamFailed { source: e })
-        .fold(tip, move |_, block| {
-            handle_block(blockchain.clone(), block, fold_logger.clone())
+    let apply = stream
+        .chunks(chunk_sizes::CHAIN_PULL)
+        .fold((tip, write_queue), move |(tip, write_queue), blocks| {
+            apply_blocks(blockchain.clone(), tip, blocks, fold_logger.clone()).and_then(
+                |(tip, blocks)| {
+                    write_queue
+                        .send(blocks)
+                        .map(move |write_queue| (tip, write_queue))
+                        .map_err(|_| Error::StoreBlocksStopped)
+                },
+            )
         })
+        // dropping the queue lets the writer finish once the last chunk
+        // is stored
+        .map(|(tip, _write_queue)| tip);
+
+    // the writer is polled first so its failure is reported rather than
+    // the closed queue
+    writer.join(apply).map(|((), tip)| tip)
+}
+
+/// Checks and applies the blocks of a chunk, in order, returning the `Ref`
+/// of the last one along with the blocks, left to be stored.
+fn apply_blocks(
+    blockchain: Blockchain,
+    tip: Arc<Ref>,
+    blocks: Vec<Block>,
+    logger: Logger,
+) -> impl Future<Item = (Arc<Ref>, Vec<Block>), Error = Error> {
+    let applied = Vec::with_capacity(blocks.len());
+    stream::iter_ok(blocks).fold((tip, applied), move |(_, mut applied), block| {
+        handle_block(blockchain.clone(), block, logger.clone()).map(move |(tip, block)| {
+            applied.push(block);
+            (tip, applied)
+        })
+    })
 }
 
 fn handle_block(
     mut blockchain: Blockchain,
     block: Block,
     logger: Logger,
-) -> impl Future<Item = Arc<Ref>, Error = Error> {
+) -> impl Future<Item = (Arc<Ref>, Block), Error = Error> {
     let header = block.header();
     trace!(
         logger,
@@ -126,7 +580,140 @@ fn handle_block(
         })
         .and_then(move |post_checked| {
             end_blockchain
-                .apply_and_store_block(post_checked, block)
+                .apply_block(post_checked, &block)
                 .map_err(|e| Error::ApplyBlockFailed { source: e })
+                .map(move |block_ref| (block_ref, block))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pull_headers_until, Error};
+    use crate::{
+        blockcfg::{Header, HeaderHash},
+        blockchain::testing::ChainBuilder,
+    };
+    use chain_core::property::ChainLength as _;
+    use std::vec;
+    use tokio::prelude::*;
+
+    // the number of headers sent by the peers for each request
+    const MAX_HEADERS: usize = 2000;
+
+    type Pull = future::FutureResult<stream::IterOk<vec::IntoIter<Header>, Error>, Error>;
+
+    /// the headers following `from` in `headers`, at most `MAX_HEADERS`
+    /// of them, as a peer would send them
+    fn pull_from(headers: &[Header], from: HeaderHash) -> Pull {
+        let start = headers
+            .iter()
+            .position(|header| header.hash() == from)
+            .map_or(0, |index| index + 1);
+        let end = usize::min(start + MAX_HEADERS, headers.len());
+        future::ok(stream::iter_ok(headers[start..end].to_vec()))
+    }
+
+    #[test]
+    fn headers_are_pulled_past_the_limit_of_a_request() {
+        let chain = ChainBuilder::new(3000);
+        let block0 = &chain.block0().header;
+        let headers: Vec<Header> = chain
+            .branch(block0, 1..=2500)
+            .into_iter()
+            .map(|block| block.header)
+            .collect();
+        let peer_tip_hash = headers.last().unwrap().hash();
+
+        let block_ids = pull_headers_until(
+            block0.hash(),
+            block0.chain_length(),
+            peer_tip_hash,
+            |from| pull_from(&headers, from),
+        )
+        .wait()
+        .unwrap();
+
+        let expected: Vec<HeaderHash> = headers.iter().map(Header::hash).collect();
+        assert_eq!(block_ids, expected);
+    }
+
+    #[test]
+    fn headers_not_reaching_the_peer_tip_are_rejected() {
+        let chain = ChainBuilder::new(3000);
+        let block0 = &chain.block0().header;
+        let headers: Vec<Header> = chain
+            .branch(block0, 1..=2500)
+            .into_iter()
+            .map(|block| block.header)
+            .collect();
+        let peer_tip_hash = chain.block(&headers[2499], chain.date(2600)).header.hash();
+
+        match pull_headers_until(
+            block0.hash(),
+            block0.chain_length(),
+            peer_tip_hash,
+            |from| pull_from(&headers, from),
+        )
+        .wait()
+        {
+            Err(Error::IncompleteHeaderChain(hash)) => assert_eq!(hash, peer_tip_hash),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-bench"))]
+mod bench {
+    use super::{bootstrap_from_stream, Error, Progress};
+    use crate::{
+        blockcfg::Block,
+        blockchain::{testing::ChainBuilder, Blockchain},
+        settings::start::config::ServedBlocks,
+    };
+    use chain_storage::memory::MemoryBlockStore;
+    use slog::{Discard, Logger};
+    use std::time::Duration;
+    use test::Bencher;
+    use tokio::{prelude::*, runtime::Runtime};
+
+    // all in the first epoch
+    const BLOCKS: u32 = 500;
+    const SLOTS_PER_EPOCH: u32 = 720;
+
+    /// a BFT block0 with a single leader and the chain of blocks signed by
+    /// this leader, as a bootstrap peer would send them
+    fn chain() -> (Block, Vec<Block>) {
+        let chain = ChainBuilder::new(SLOTS_PER_EPOCH);
+        let blocks = chain.branch(&chain.block0().header, 1..=BLOCKS);
+        (chain.block0().clone(), blocks)
+    }
+
+    /// The time of an iteration is the time to check, apply and store
+    /// `BLOCKS` blocks into an in-memory storage, once they have been
+    /// received: the blocks are read from memory, the network is not part
+    /// of the measure. Dividing `BLOCKS` by this time gives the number of
+    /// blocks per second the bootstrap processes.
+    #[bench]
+    fn apply_and_store_500_blocks(b: &mut Bencher) {
+        let (block0, blocks) = chain();
+        let logger = Logger::root(Discard, o!());
+        // the blocks are written to the storage by a task of the runtime
+        let mut runtime = Runtime::new().unwrap();
+        b.iter(|| {
+            let mut blockchain = Blockchain::new(
+                Box::new(MemoryBlockStore::new()),
+                Duration::from_secs(3600),
+                ServedBlocks::All,
+            );
+            let branch = blockchain.load_from_block0(block0.clone()).wait().unwrap();
+            let tip = branch.get_ref().wait().unwrap();
+            let stream = stream::iter_ok::<_, Error>(blocks.clone());
+            let logger = logger.clone();
+            runtime
+                .block_on(future::lazy(move || {
+                    bootstrap_from_stream(blockchain, tip, stream, Progress::default(), logger)
+                }))
+                .unwrap()
         })
+    }
 }
@@ -34,6 +34,7 @@ pub fn connect(
     let builder = Some(ClientBuilder {
         channels,
         logger: state.logger,
+        remote_address: addr,
     });
     let cf = grpc::connect(addr, Some(node_id), state.global.executor.clone());
     let handle = ConnectHandle { receiver };
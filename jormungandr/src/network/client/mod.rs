@@ -13,6 +13,7 @@ use crate::{
     intercom::{self, BlockMsg, ClientMsg},
     utils::task::TaskMessageBox,
 };
+use chain_core::property::Serialize as _;
 use network_core::client as core_client;
 use network_core::client::{BlockService, FragmentService, GossipService, P2pService};
 use network_core::error as core_error;
@@ -20,6 +21,7 @@ use network_core::subscription::{BlockEvent, ChainPullRequest};
 
 use futures::prelude::*;
 use slog::Logger;
+use std::net::SocketAddr;
 
 pub use self::connect::{connect, ConnectError, ConnectFuture, ConnectHandle};
 
@@ -46,6 +48,7 @@ where
 struct ClientBuilder {
     pub logger: Logger,
     pub channels: Channels,
+    pub remote_address: SocketAddr,
 }
 
 impl<S> Client<S>
@@ -90,6 +93,7 @@ where
         let fragment_sink = FragmentProcessor::new(
             builder.channels.transaction_box,
             remote_node_id,
+            Some(builder.remote_address),
             global_state.clone(),
             &logger,
         );
@@ -341,6 +345,8 @@ where
     // retrieval.
     fn pull_blocks_to_tip(&mut self, req: ChainPullRequest<HeaderHash>) {
         let block_box = self.block_sink.message_box();
+        let node_id = self.remote_node_id();
+        let global_state = self.global_state.clone();
         let logger = self.logger.clone();
         let err_logger = logger.clone();
         self.global_state.spawn(
@@ -352,16 +358,25 @@ where
                 .and_then(move |stream| {
                     let stream_err_logger = logger.clone();
                     let sink_err_logger = logger.clone();
-                    let stream = stream.map_err(move |e| {
-                        info!(
-                            stream_err_logger,
-                            "PullBlocksToTip response stream failed: {:?}", e
-                        );
-                    });
+                    let stream = stream
+                        .map_err(move |e| {
+                            info!(
+                                stream_err_logger,
+                                "PullBlocksToTip response stream failed: {:?}", e
+                            );
+                        })
+                        .map(move |block| {
+                            let block_size =
+                                block.serialize_as_vec().map_or(0, |bytes| bytes.len());
+                            global_state.peers.add_bytes_received(node_id, block_size);
+                            (block, node_id)
+                        });
                     InboundProcessing::with_unary(
                         block_box.clone(),
                         logger.clone(),
-                        |block, reply| BlockMsg::NetworkBlock(block, reply),
+                        |(block, node_id), reply| {
+                            BlockMsg::NetworkBlock(block, Some(node_id), reply)
+                        },
                     )
                     .sink_map_err(move |e| {
                         warn!(sink_err_logger, "pulled block validation failed: {:?}", e)
@@ -384,6 +399,8 @@ where
 {
     fn solicit_blocks(&mut self, block_ids: &[HeaderHash]) {
         let block_box = self.block_sink.message_box();
+        let node_id = self.remote_node_id();
+        let global_state = self.global_state.clone();
         let logger = self.logger.clone();
         let err_logger = logger.clone();
         self.global_state.spawn(
@@ -398,16 +415,25 @@ where
                 .and_then(move |stream| {
                     let stream_err_logger = logger.clone();
                     let sink_err_logger = logger.clone();
-                    let stream = stream.map_err(move |e| {
-                        info!(
-                            stream_err_logger,
-                            "GetBlocks response stream failed: {:?}", e
-                        );
-                    });
+                    let stream = stream
+                        .map_err(move |e| {
+                            info!(
+                                stream_err_logger,
+                                "GetBlocks response stream failed: {:?}", e
+                            );
+                        })
+                        .map(move |block| {
+                            let block_size =
+                                block.serialize_as_vec().map_or(0, |bytes| bytes.len());
+                            global_state.peers.add_bytes_received(node_id, block_size);
+                            (block, node_id)
+                        });
                     InboundProcessing::with_unary(
                         block_box.clone(),
                         logger.clone(),
-                        |block, reply| BlockMsg::NetworkBlock(block, reply),
+                        |(block, node_id), reply| {
+                            BlockMsg::NetworkBlock(block, Some(node_id), reply)
+                        },
                     )
                     .sink_map_err(move |e| {
                         warn!(sink_err_logger, "network block validation failed: {:?}", e)
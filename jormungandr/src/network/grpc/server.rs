@@ -24,7 +24,6 @@ pub fn run_listen_socket(
             let fold_logger = state.logger().clone();
             let err_logger = state.logger().clone();
             let node_server = NodeService::new(channels, state);
-            let mut server = Server::new(node_server);
 
             let future = listener_stream
                 .map_err(move |err| {
@@ -36,8 +35,8 @@ pub fn run_listen_socket(
                 })
                 .filter_map(move |stream| {
                     // received incoming connection
-                    let conn_logger = match stream.peer_addr() {
-                        Ok(addr) => fold_logger.new(o!("peer_addr" => addr)),
+                    let peer_addr = match stream.peer_addr() {
+                        Ok(addr) => addr,
                         Err(e) => {
                             debug!(
                                 fold_logger,
@@ -46,12 +45,15 @@ pub fn run_listen_socket(
                             return None;
                         }
                     };
+                    let conn_logger = fold_logger.new(o!("peer_addr" => peer_addr));
                     info!(
                         conn_logger,
                         "incoming connection on {}",
                         stream.local_addr().unwrap(),
                     );
 
+                    // the service knows the address of the peer it serves
+                    let mut server = Server::new(node_server.for_connection(peer_addr));
                     let conn = server.serve(stream).then(move |res| {
                         use network_grpc::server::Error;
 
@@ -57,7 +57,10 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
-pub use self::bootstrap::Error as BootstrapError;
+pub use self::bootstrap::{
+    Error as BootstrapError, Progress as BootstrapProgress,
+    ProgressReport as BootstrapProgressReport,
+};
 
 #[derive(Debug)]
 pub struct ListenError {
@@ -290,6 +293,17 @@ fn handle_network_input(
             reply.reply_ok(stats);
             Ok(())
         }
+        NetworkMsg::ReportMisbehavior(node_id, misbehavior) => {
+            if state.peers.report_misbehavior(node_id, misbehavior) {
+                state.topology.evict_node(node_id);
+            }
+            Ok(())
+        }
+        NetworkMsg::PeerReputations(reply) => {
+            let reputations = state.peers.reputations();
+            reply.reply_ok(reputations);
+            Ok(())
+        }
     })
 }
 
@@ -361,6 +375,14 @@ fn connect_and_propagate_with<F>(
         );
         return;
     }
+    if state.peers.is_banned(node_id) {
+        debug!(
+            state.logger(),
+            "not connecting to banned peer";
+            "node_id" => %node_id,
+        );
+        return;
+    }
     let peer = Peer::new(addr, Protocol::Grpc);
     let conn_state = ConnectionState::new(state.clone(), &peer);
     let conn_logger = conn_state
@@ -420,6 +442,7 @@ pub fn bootstrap(
     config: &Configuration,
     blockchain: NewBlockchain,
     branch: Tip,
+    progress: BootstrapProgress,
     logger: &Logger,
 ) -> Result<bool, bootstrap::Error> {
     if config.protocol != Protocol::Grpc {
@@ -428,36 +451,29 @@ pub fn bootstrap(
 
     if config.trusted_peers.is_empty() {
         warn!(logger, "No trusted peers joinable to bootstrap the network");
+        return Ok(false);
     }
 
-    let mut bootstrapped = false;
+    let peers = trusted_peers_shuffled(&config)
+        .into_iter()
+        .map(|address| Peer::new(address, Protocol::Grpc))
+        .collect();
+    let res = bootstrap::bootstrap_from_peers(peers, blockchain, branch, progress, logger.clone());
 
-    for address in trusted_peers_shuffled(&config) {
-        let logger = logger.new(o!("peer_addr" => address.to_string()));
-        let peer = Peer::new(address, Protocol::Grpc);
-        let res = bootstrap::bootstrap_from_peer(
-            peer,
-            blockchain.clone(),
-            branch.clone(),
-            logger.clone(),
-        );
-
-        match res {
-            Err(bootstrap::Error::Connect { source: e }) => {
-                warn!(logger, "unable to reach peer for initial bootstrap"; "reason" => %e);
-            }
-            Err(e) => {
-                warn!(logger, "initial bootstrap failed"; "error" => ?e);
-            }
-            Ok(_) => {
-                info!(logger, "initial bootstrap completed");
-                bootstrapped = true;
-                break;
-            }
+    match res {
+        Err(bootstrap::Error::NoPeerAvailable) => {
+            warn!(logger, "unable to reach any peer for initial bootstrap");
+            Ok(false)
+        }
+        Err(e) => {
+            warn!(logger, "initial bootstrap failed"; "error" => ?e);
+            Ok(false)
+        }
+        Ok(_) => {
+            info!(logger, "initial bootstrap completed");
+            Ok(true)
         }
     }
-
-    Ok(bootstrapped)
 }
 
 /// Queries the trusted peers for a block identified with the hash.
@@ -1,4 +1,7 @@
 mod peer_map;
+mod reputation;
+
+pub use self::reputation::{Misbehavior, Reputation};
 
 use super::topology;
 use crate::blockcfg::{Block, Fragment, Header, HeaderHash};
@@ -312,6 +315,7 @@ pub struct PeerStats {
     last_block_received: Option<SystemTime>,
     last_fragment_received: Option<SystemTime>,
     last_gossip_received: Option<SystemTime>,
+    bytes_received: u64,
 }
 
 impl Default for PeerStats {
@@ -321,6 +325,7 @@ impl Default for PeerStats {
             last_block_received: None,
             last_fragment_received: None,
             last_gossip_received: None,
+            bytes_received: 0,
         }
     }
 }
@@ -342,6 +347,12 @@ impl PeerStats {
         self.created
     }
 
+    /// Serialized size of the blocks, headers and fragments received
+    /// from the peer.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
     pub fn last_activity(&self) -> SystemTime {
         use std::cmp::max;
 
@@ -362,6 +373,7 @@ impl PeerStats {
 /// all network connection tasks.
 pub struct Peers {
     mutex: Mutex<peer_map::PeerMap>,
+    reputations: Mutex<reputation::Reputations>,
     logger: Logger,
 }
 
@@ -369,6 +381,7 @@ impl Peers {
     pub fn new(capacity: usize, logger: Logger) -> Self {
         Peers {
             mutex: Mutex::new(peer_map::PeerMap::new(capacity)),
+            reputations: Mutex::new(reputation::Reputations::default()),
             logger,
         }
     }
@@ -552,6 +565,13 @@ impl Peers {
         }
     }
 
+    pub fn add_bytes_received(&self, node_id: topology::NodeId, bytes: usize) {
+        let mut map = self.mutex.lock().unwrap();
+        if let Some(mut entry) = map.entry(node_id) {
+            entry.stats().bytes_received += bytes as u64;
+        }
+    }
+
     pub fn fetch_blocks(&self, hashes: Vec<HeaderHash>) {
         let mut map = self.mutex.lock().unwrap();
         if let Some((node_id, comms)) = map.next_peer_for_block_fetch() {
@@ -629,4 +649,35 @@ impl Peers {
         let map = self.mutex.lock().unwrap();
         map.stats()
     }
+
+    /// Lowers the reputation of the peer, disconnecting it if it gets
+    /// banned for this misbehavior.
+    ///
+    /// Returns true if the peer got banned.
+    pub fn report_misbehavior(&self, id: topology::NodeId, misbehavior: Misbehavior) -> bool {
+        let banned = {
+            let mut reputations = self.reputations.lock().unwrap();
+            reputations.penalize(id, misbehavior)
+        };
+        if banned {
+            info!(
+                self.logger,
+                "banning peer";
+                "node_id" => %id,
+                "misbehavior" => ?misbehavior,
+            );
+            self.remove_peer(id);
+        }
+        banned
+    }
+
+    pub fn is_banned(&self, id: topology::NodeId) -> bool {
+        let reputations = self.reputations.lock().unwrap();
+        reputations.is_banned(id)
+    }
+
+    pub fn reputations(&self) -> Vec<(topology::NodeId, Reputation)> {
+        let reputations = self.reputations.lock().unwrap();
+        reputations.reputations()
+    }
 }
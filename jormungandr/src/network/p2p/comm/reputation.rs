@@ -0,0 +1,210 @@
+use super::super::topology::NodeId;
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Penalty points at which a peer gets banned.
+const BAN_THRESHOLD: u32 = 100;
+
+/// For how long a peer stays banned.
+const BAN_DURATION: Duration = Duration::from_secs(30 * 60);
+
+/// One penalty point is forgiven every period.
+const PENALTY_DECAY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Penalty points of each invalid fragment.
+const INVALID_FRAGMENT_PENALTY: u32 = 5;
+
+/// Misbehavior of a peer lowering its reputation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// The peer sent a block failing validation.
+    InvalidBlock,
+    /// The peer sent the given number of fragments which are invalid on
+    /// their own, independently of the ledger state. The fragments rejected
+    /// by the ledger are not a misbehavior: an honest peer relays them as
+    /// well when it has not seen the same tip or pending fragments.
+    InvalidFragments(u32),
+}
+
+impl Misbehavior {
+    fn penalty(self) -> u32 {
+        match self {
+            Misbehavior::InvalidBlock => 50,
+            Misbehavior::InvalidFragments(count) => count.saturating_mul(INVALID_FRAGMENT_PENALTY),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Reputation {
+    penalty: u32,
+    last_penalized: SystemTime,
+    invalid_blocks: u64,
+    invalid_fragments: u64,
+    banned_until: Option<SystemTime>,
+}
+
+impl Reputation {
+    fn new(now: SystemTime) -> Self {
+        Reputation {
+            penalty: 0,
+            last_penalized: now,
+            invalid_blocks: 0,
+            invalid_fragments: 0,
+            banned_until: None,
+        }
+    }
+
+    fn penalty_at(&self, now: SystemTime) -> u32 {
+        let elapsed = now
+            .duration_since(self.last_penalized)
+            .unwrap_or(Duration::from_secs(0));
+        let forgiven = elapsed.as_secs() / PENALTY_DECAY_PERIOD.as_secs();
+        if forgiven >= self.penalty as u64 {
+            0
+        } else {
+            self.penalty - forgiven as u32
+        }
+    }
+
+    /// Returns true if the peer got banned for this misbehavior.
+    fn penalize(&mut self, misbehavior: Misbehavior, now: SystemTime) -> bool {
+        match misbehavior {
+            Misbehavior::InvalidBlock => self.invalid_blocks += 1,
+            Misbehavior::InvalidFragments(count) => self.invalid_fragments += count as u64,
+        }
+        self.penalty = self.penalty_at(now).saturating_add(misbehavior.penalty());
+        self.last_penalized = now;
+        if self.penalty >= BAN_THRESHOLD && !self.is_banned_at(now) {
+            self.penalty = 0;
+            self.banned_until = Some(now + BAN_DURATION);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_banned_at(&self, now: SystemTime) -> bool {
+        self.banned_until.map_or(false, |until| until > now)
+    }
+
+    /// Neither banned nor penalized anymore, the record can be dropped.
+    fn is_clean_at(&self, now: SystemTime) -> bool {
+        !self.is_banned_at(now) && self.penalty_at(now) == 0
+    }
+
+    /// Current penalty points, the peer is banned when they reach
+    /// the threshold.
+    pub fn penalty(&self) -> u32 {
+        self.penalty_at(SystemTime::now())
+    }
+
+    pub fn invalid_blocks(&self) -> u64 {
+        self.invalid_blocks
+    }
+
+    pub fn invalid_fragments(&self) -> u64 {
+        self.invalid_fragments
+    }
+
+    /// The end of the ban, if the peer is currently banned.
+    pub fn banned_until(&self) -> Option<SystemTime> {
+        self.banned_until.filter(|until| *until > SystemTime::now())
+    }
+}
+
+/// Reputations of the peers, kept independently of the connections
+/// so that disconnecting does not clear a peer's record.
+#[derive(Default)]
+pub struct Reputations {
+    map: HashMap<NodeId, Reputation>,
+}
+
+impl Reputations {
+    /// Returns true if the peer got banned for this misbehavior.
+    pub fn penalize(&mut self, id: NodeId, misbehavior: Misbehavior) -> bool {
+        let now = SystemTime::now();
+        // drop the records of the peers which have been forgiven
+        self.map
+            .retain(|_, reputation| !reputation.is_clean_at(now));
+        self.map
+            .entry(id)
+            .or_insert_with(|| Reputation::new(now))
+            .penalize(misbehavior, now)
+    }
+
+    pub fn is_banned(&self, id: NodeId) -> bool {
+        let now = SystemTime::now();
+        self.map
+            .get(&id)
+            .map_or(false, |reputation| reputation.is_banned_at(now))
+    }
+
+    pub fn reputations(&self) -> Vec<(NodeId, Reputation)> {
+        self.map
+            .iter()
+            .map(|(&id, reputation)| (id, reputation.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_id(byte: u8) -> NodeId {
+        NodeId(poldercast::Id::from([byte; 32]))
+    }
+
+    #[test]
+    fn penalty_decays_over_time() {
+        let start = SystemTime::now();
+        let mut reputation = Reputation::new(start);
+
+        assert!(!reputation.penalize(Misbehavior::InvalidBlock, start));
+        assert_eq!(reputation.penalty_at(start), 50);
+        assert_eq!(reputation.penalty_at(start + PENALTY_DECAY_PERIOD * 10), 40);
+        assert_eq!(reputation.penalty_at(start + PENALTY_DECAY_PERIOD * 50), 0);
+        assert!(reputation.is_clean_at(start + PENALTY_DECAY_PERIOD * 60));
+    }
+
+    #[test]
+    fn reaching_the_threshold_bans_the_peer() {
+        let start = SystemTime::now();
+        let mut reputation = Reputation::new(start);
+
+        assert!(!reputation.penalize(Misbehavior::InvalidFragments(19), start));
+        assert!(!reputation.is_banned_at(start));
+        assert!(reputation.penalize(Misbehavior::InvalidFragments(1), start));
+        assert!(reputation.is_banned_at(start));
+        assert_eq!(reputation.invalid_fragments(), 20);
+        assert!(!reputation.is_clean_at(start + BAN_DURATION / 2));
+        assert!(!reputation.is_banned_at(start + BAN_DURATION));
+        assert!(reputation.is_clean_at(start + BAN_DURATION));
+    }
+
+    #[test]
+    fn decayed_penalty_does_not_ban() {
+        let start = SystemTime::now();
+        let mut reputation = Reputation::new(start);
+
+        assert!(!reputation.penalize(Misbehavior::InvalidBlock, start));
+        let later = start + PENALTY_DECAY_PERIOD * 5;
+        assert!(!reputation.penalize(Misbehavior::InvalidBlock, later));
+        assert_eq!(reputation.penalty_at(later), 95);
+        assert_eq!(reputation.invalid_blocks(), 2);
+    }
+
+    #[test]
+    fn peers_are_banned_independently() {
+        let mut reputations = Reputations::default();
+
+        assert!(!reputations.penalize(node_id(1), Misbehavior::InvalidBlock));
+        assert!(!reputations.penalize(node_id(2), Misbehavior::InvalidBlock));
+        assert!(reputations.penalize(node_id(1), Misbehavior::InvalidBlock));
+        assert!(reputations.is_banned(node_id(1)));
+        assert!(!reputations.is_banned(node_id(2)));
+        assert_eq!(reputations.reputations().len(), 2);
+    }
+}
@@ -3,6 +3,7 @@
 
 use bincode;
 use chain_core::property;
+use jormungandr_lib::interfaces;
 use network_core::gossip::{self, Node as _};
 use poldercast::topology::{Cyclon, Module, Rings, Topology, Vicinity};
 use poldercast::Subscription;
@@ -164,6 +165,14 @@ impl fmt::Display for NodeId {
     }
 }
 
+impl From<NodeId> for interfaces::NodeId {
+    fn from(node_id: NodeId) -> Self {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(node_id.0.as_ref());
+        interfaces::NodeId::from(bytes)
+    }
+}
+
 /// object holding the P2pTopology of the Node
 pub struct P2pTopology {
     lock: RwLock<Topology>,
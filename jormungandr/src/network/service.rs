@@ -16,12 +16,14 @@ use network_core::error as core_error;
 use network_core::gossip::{Gossip, Node as _};
 use network_core::server::{BlockService, FragmentService, GossipService, Node, P2pService};
 use slog::Logger;
+use std::net::SocketAddr;
 
 #[derive(Clone)]
 pub struct NodeService {
     channels: Channels,
     global_state: GlobalStateR,
     logger: Logger,
+    remote_address: Option<SocketAddr>,
 }
 
 impl NodeService {
@@ -32,6 +34,15 @@ impl NodeService {
                 .logger()
                 .new(o!(::log::KEY_SUB_TASK => "server")),
             global_state,
+            remote_address: None,
+        }
+    }
+
+    /// the service serving the incoming connection from the given address
+    pub fn for_connection(&self, remote_address: SocketAddr) -> Self {
+        NodeService {
+            remote_address: Some(remote_address),
+            ..self.clone()
         }
     }
 
@@ -47,6 +58,20 @@ where
     fn subscription_logger(&self, subscriber: <Self as P2pService>::NodeId) -> Logger {
         self.logger.new(o!("node_id" => subscriber.to_string()))
     }
+
+    fn check_not_banned(
+        &self,
+        subscriber: <Self as P2pService>::NodeId,
+    ) -> Result<(), core_error::Error> {
+        if self.global_state.peers.is_banned(subscriber) {
+            Err(core_error::Error::new(
+                core_error::Code::FailedPrecondition,
+                "the node is temporarily banned",
+            ))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl Node for NodeService {
@@ -165,7 +190,8 @@ impl BlockService for NodeService {
         future::ok(InboundProcessing::with_unary(
             self.channels.block_box.clone(),
             self.logger.clone(),
-            |block, handle| BlockMsg::NetworkBlock(block, handle),
+            // the node uploading the blocks is not known here
+            |block, handle| BlockMsg::NetworkBlock(block, None, handle),
         ))
     }
 
@@ -177,6 +203,9 @@ impl BlockService for NodeService {
     where
         In: Stream<Item = Self::Header, Error = core_error::Error> + Send + 'static,
     {
+        if let Err(e) = self.check_not_banned(subscriber) {
+            return future::err(e);
+        }
         let logger = self.subscription_logger(subscriber);
 
         subscription::process_block_announcements(
@@ -253,11 +282,15 @@ impl FragmentService for NodeService {
     where
         S: Stream<Item = Self::Fragment, Error = core_error::Error> + Send + 'static,
     {
+        if let Err(e) = self.check_not_banned(subscriber) {
+            return future::err(e);
+        }
         let logger = self.subscription_logger(subscriber);
 
         subscription::process_fragments(
             inbound,
             subscriber,
+            self.remote_address,
             self.global_state.clone(),
             self.channels.transaction_box.clone(),
             &logger,
@@ -284,6 +317,9 @@ impl GossipService for NodeService {
     where
         In: Stream<Item = Gossip<Self::Node>, Error = core_error::Error> + Send + 'static,
     {
+        if let Err(e) = self.check_not_banned(subscriber) {
+            return future::err(e);
+        }
         let logger = self.subscription_logger(subscriber);
 
         subscription::process_gossip(inbound, subscriber, self.global_state.clone(), &logger);
@@ -9,11 +9,13 @@ use crate::{
     settings::start::network::Configuration,
     utils::async_msg::MessageBox,
 };
+use chain_core::property::Serialize as _;
 use futures::prelude::*;
 use jormungandr_lib::interfaces::FragmentOrigin;
 use network_core::error as core_error;
 use network_core::gossip::{Gossip, Node as _};
 use slog::Logger;
+use std::net::SocketAddr;
 
 pub fn process_block_announcements<S>(
     inbound: S,
@@ -48,13 +50,20 @@ pub fn process_block_announcements<S>(
 pub fn process_fragments<S>(
     inbound: S,
     node_id: NodeId,
+    node_address: Option<SocketAddr>,
     global_state: GlobalStateR,
     fragment_box: MessageBox<TransactionMsg>,
     logger: &Logger,
 ) where
     S: Stream<Item = Fragment, Error = core_error::Error> + Send + 'static,
 {
-    let sink = FragmentProcessor::new(fragment_box, node_id, global_state.clone(), logger);
+    let sink = FragmentProcessor::new(
+        fragment_box,
+        node_id,
+        node_address,
+        global_state.clone(),
+        logger,
+    );
     let logger = sink.logger.clone();
     let stream_err_logger = logger.clone();
     let stream = inbound
@@ -141,6 +150,7 @@ impl BlockAnnouncementProcessor {
 pub struct FragmentProcessor {
     mbox: MessageBox<TransactionMsg>,
     node_id: NodeId,
+    origin: FragmentOrigin,
     global_state: GlobalStateR,
     logger: Logger,
 }
@@ -149,6 +159,7 @@ impl FragmentProcessor {
     pub fn new(
         mbox: MessageBox<TransactionMsg>,
         node_id: NodeId,
+        node_address: Option<SocketAddr>,
         global_state: GlobalStateR,
         logger: &Logger,
     ) -> Self {
@@ -156,6 +167,7 @@ impl FragmentProcessor {
         FragmentProcessor {
             mbox,
             node_id,
+            origin: FragmentOrigin::network(node_id.into(), node_address),
             global_state,
             logger,
         }
@@ -180,8 +192,9 @@ impl GossipProcessor {
 
     pub fn process_item(&self, gossip: Gossip<NodeData>) {
         let (nodes, filtered_out): (Vec<_>, Vec<_>) = gossip.into_nodes().partition(|node| {
-            filter_gossip_node(node, &self.global_state.config)
-                || (node.id() == self.node_id && node.address().is_none())
+            (filter_gossip_node(node, &self.global_state.config)
+                || (node.id() == self.node_id && node.address().is_none()))
+                && !self.global_state.peers.is_banned(node.id())
         });
         if filtered_out.len() > 0 {
             debug!(self.logger, "nodes dropped from gossip: {:?}", filtered_out);
@@ -201,6 +214,11 @@ impl Sink for BlockAnnouncementProcessor {
     type SinkError = ();
 
     fn start_send(&mut self, header: Header) -> StartSend<Header, ()> {
+        if self.global_state.peers.is_banned(self.node_id) {
+            info!(self.logger, "peer is banned, closing the subscription");
+            return Err(());
+        }
+        let header_size = header.serialize_as_vec().map_or(0, |bytes| bytes.len());
         let polled = self
             .mbox
             .start_send(BlockMsg::AnnouncedBlock(header, self.node_id))
@@ -214,6 +232,9 @@ impl Sink for BlockAnnouncementProcessor {
         match polled {
             AsyncSink::Ready => {
                 self.global_state.peers.refresh_peer_on_block(self.node_id);
+                self.global_state
+                    .peers
+                    .add_bytes_received(self.node_id, header_size);
                 Ok(AsyncSink::Ready)
             }
             AsyncSink::NotReady(BlockMsg::AnnouncedBlock(header, _)) => {
@@ -249,10 +270,19 @@ impl Sink for FragmentProcessor {
     type SinkError = ();
 
     fn start_send(&mut self, fragments: Vec<Fragment>) -> StartSend<Self::SinkItem, ()> {
+        if self.global_state.peers.is_banned(self.node_id) {
+            info!(self.logger, "peer is banned, closing the subscription");
+            return Err(());
+        }
+        let fragments_size = fragments
+            .iter()
+            .map(|fragment| fragment.to_raw().size_bytes_plus_size())
+            .sum();
         let polled = self
             .mbox
-            .start_send(TransactionMsg::SendTransaction(
-                FragmentOrigin::Network,
+            .start_send(TransactionMsg::SendPeerTransaction(
+                self.node_id,
+                self.origin,
                 fragments,
             ))
             .map_err(|e| {
@@ -267,9 +297,12 @@ impl Sink for FragmentProcessor {
                 self.global_state
                     .peers
                     .refresh_peer_on_fragment(self.node_id);
+                self.global_state
+                    .peers
+                    .add_bytes_received(self.node_id, fragments_size);
                 Ok(AsyncSink::Ready)
             }
-            AsyncSink::NotReady(TransactionMsg::SendTransaction(_, fragments)) => {
+            AsyncSink::NotReady(TransactionMsg::SendPeerTransaction(_, _, fragments)) => {
                 Ok(AsyncSink::NotReady(fragments))
             }
             AsyncSink::NotReady(_) => unreachable!(),
@@ -1,9 +1,10 @@
 use actix_web::error::{ErrorBadRequest, ErrorInternalServerError, ErrorServiceUnavailable};
-use actix_web::{http, Json, Responder, State};
-use actix_web::{Error, HttpResponse};
+use actix_web::{http, ws, Json, Responder, State};
+use actix_web::{Error, HttpRequest, HttpResponse};
 
 use futures::{Future, IntoFuture};
 
+use super::subscriptions::Session;
 use crate::explorer::graphql::GraphQLRequest;
 pub use crate::rest::Context;
 
@@ -44,3 +45,14 @@ pub fn graphql(context: State<Context>, data: Json<GraphQLRequest>) -> ActixFutu
         })
         .map_err(|err| ErrorInternalServerError(err))
 }
+
+/// Upgrade the connection to a WebSocket carrying GraphQL subscriptions
+pub fn subscriptions(req: &HttpRequest<Context>) -> Result<HttpResponse, Error> {
+    let explorer = req
+        .state()
+        .try_full()?
+        .explorer
+        .clone()
+        .ok_or(ErrorServiceUnavailable("Explorer not enabled"))?;
+    ws::start(req, Session::new(explorer))
+}
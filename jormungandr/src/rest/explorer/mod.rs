@@ -1,4 +1,5 @@
 mod handlers;
+mod subscriptions;
 
 use actix_web::dev::Resource;
 
@@ -7,7 +8,10 @@ pub fn resources() -> Vec<(
     &'static dyn Fn(&mut Resource<handlers::Context>),
 )> {
     vec![
-        ("/graphql", &|r| r.post().with_async(handlers::graphql)),
+        ("/graphql", &|r| {
+            r.post().with_async(handlers::graphql);
+            r.get().f(handlers::subscriptions);
+        }),
         ("/graphiql", &|r| r.get().with(handlers::graphiql)),
     ]
 }
@@ -0,0 +1,233 @@
+//! GraphQL subscriptions over WebSocket, following the `graphql-ws`
+//! protocol of the Apollo subscriptions transport.
+//!
+//! Each time a block becomes the tip of the explorer's longest chain, the
+//! subscriptions of the session are executed against the `Subscription`
+//! root of the tip, and the results are sent to the client. The results in
+//! which all the subscribed fields are null or empty are not sent.
+//!
+//! When the tip switches to another branch, the subscriptions are executed
+//! for each block of the new branch after the fork point. A session that
+//! does not keep up with the new tips is closed.
+
+use actix_web::actix::{
+    Actor, ActorContext as _, ActorFuture as _, AsyncContext as _, StreamHandler, WrapFuture as _,
+};
+use actix_web::ws;
+use std::collections::HashMap;
+
+use crate::explorer::graphql::{self, GraphQLRequest, Subscription};
+use crate::explorer::Explorer;
+use crate::rest::Context;
+
+pub struct Session {
+    explorer: Explorer,
+    subscriptions: HashMap<String, GraphQLRequest>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    ConnectionInit,
+    Start { id: String, payload: StartPayload },
+    Stop { id: String },
+    ConnectionTerminate,
+}
+
+#[derive(Deserialize)]
+struct StartPayload {
+    query: String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables: Option<juniper::InputValue>,
+}
+
+/// The result of the execution of a subscription for a new tip
+enum Outcome {
+    Data(serde_json::Value),
+    Error(serde_json::Value),
+    Nothing,
+}
+
+impl Session {
+    pub fn new(explorer: Explorer) -> Self {
+        Session {
+            explorer,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    fn handle_text(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self, Context>) {
+        let message = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(err) => {
+                let message = json!({
+                    "type": "connection_error",
+                    "payload": { "message": err.to_string() },
+                });
+                return ctx.text(message.to_string());
+            }
+        };
+
+        match message {
+            ClientMessage::ConnectionInit => {
+                ctx.text(json!({ "type": "connection_ack" }).to_string());
+            }
+            ClientMessage::Start { id, payload } => {
+                let request = GraphQLRequest::new(
+                    as_query(&payload.query),
+                    payload.operation_name,
+                    payload.variables,
+                );
+                self.subscriptions.insert(id, request);
+            }
+            ClientMessage::Stop { id } => {
+                self.subscriptions.remove(&id);
+                ctx.text(json!({ "type": "complete", "id": id }).to_string());
+            }
+            ClientMessage::ConnectionTerminate => ctx.stop(),
+        }
+    }
+}
+
+impl Actor for Session {
+    type Context = ws::WebsocketContext<Self, Context>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // the stream ends when the session falls too far behind the tip,
+        // which stops the session
+        ctx.add_stream(self.explorer.subscribers.subscribe());
+    }
+}
+
+impl StreamHandler<ws::Message, ws::ProtocolError> for Session {
+    fn handle(&mut self, message: ws::Message, ctx: &mut Self::Context) {
+        match message {
+            ws::Message::Ping(message) => ctx.pong(&message),
+            ws::Message::Text(text) => self.handle_text(&text, ctx),
+            ws::Message::Close(_) => ctx.stop(),
+            ws::Message::Pong(_) | ws::Message::Binary(_) => (),
+        }
+    }
+}
+
+impl StreamHandler<Subscription, ()> for Session {
+    fn handle(&mut self, subscription: Subscription, ctx: &mut Self::Context) {
+        if self.subscriptions.is_empty() {
+            return;
+        }
+
+        let context = self.explorer.context();
+        let requests: Vec<_> = self
+            .subscriptions
+            .iter()
+            .map(|(id, request)| (id.clone(), request.clone()))
+            .collect();
+
+        // Run the subscriptions in a threadpool, as Juniper is synchronous
+        let execution = actix_threadpool::run(move || {
+            let schema = graphql::create_subscription_schema(subscription);
+            requests
+                .into_iter()
+                .map(|(id, request)| {
+                    let response = request.execute(&schema, &context);
+                    let payload = serde_json::to_value(&response)?;
+                    let outcome = if !response.is_ok() {
+                        Outcome::Error(payload)
+                    } else if is_empty(&payload["data"]) {
+                        Outcome::Nothing
+                    } else {
+                        Outcome::Data(payload)
+                    };
+                    Ok((id, outcome))
+                })
+                .collect::<Result<Vec<_>, serde_json::Error>>()
+        });
+
+        ctx.spawn(
+            execution
+                .into_actor(self)
+                .map(|outcomes, session, ctx| {
+                    for (id, outcome) in outcomes {
+                        match outcome {
+                            Outcome::Data(payload) => {
+                                let message =
+                                    json!({ "type": "data", "id": id, "payload": payload });
+                                ctx.text(message.to_string());
+                            }
+                            Outcome::Error(payload) => {
+                                // the subscription can not be executed, it
+                                // would fail again on the next tip
+                                session.subscriptions.remove(&id);
+                                let message =
+                                    json!({ "type": "error", "id": id, "payload": payload });
+                                ctx.text(message.to_string());
+                            }
+                            Outcome::Nothing => (),
+                        }
+                    }
+                })
+                .map_err(|_err, _session, _ctx| ()),
+        );
+    }
+}
+
+/// Juniper only parses queries and mutations, the selection of a
+/// subscription is executed as a query on its root
+fn as_query(subscription: &str) -> String {
+    let subscription = subscription.trim_start();
+    if subscription.starts_with("subscription") {
+        subscription.replacen("subscription", "query", 1)
+    } else {
+        subscription.to_owned()
+    }
+}
+
+/// Nothing to report for the tip: all the subscribed fields are null or
+/// empty lists
+fn is_empty(data: &serde_json::Value) -> bool {
+    match data.as_object() {
+        None => true,
+        Some(fields) => fields.values().all(|value| match value {
+            serde_json::Value::Null => true,
+            serde_json::Value::Array(values) => values.is_empty(),
+            _ => false,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{as_query, is_empty};
+
+    #[test]
+    fn subscriptions_are_executed_as_queries() {
+        assert_eq!(
+            as_query("subscription { tip { id } }"),
+            "query { tip { id } }"
+        );
+        assert_eq!(
+            as_query("\n  subscription NewBlocks($address: String!) { tip { id } }"),
+            "query NewBlocks($address: String!) { tip { id } }"
+        );
+        // only the operation type is rewritten
+        assert_eq!(
+            as_query("subscription { subscriptionTip { id } }"),
+            "query { subscriptionTip { id } }"
+        );
+        assert_eq!(as_query("query { tip { id } }"), "query { tip { id } }");
+        assert_eq!(as_query("{ tip { id } }"), "{ tip { id } }");
+    }
+
+    #[test]
+    fn null_and_empty_fields_are_nothing_to_report() {
+        assert!(is_empty(&json!(null)));
+        assert!(is_empty(&json!({})));
+        assert!(is_empty(&json!({ "tip": null, "transactions": [] })));
+        assert!(!is_empty(&json!({ "tip": { "id": "abcd" } })));
+        assert!(!is_empty(
+            &json!({ "tip": null, "transactions": [{ "id": "abcd" }] })
+        ));
+        assert!(!is_empty(&json!({ "epoch": 0 })));
+    }
+}
@@ -0,0 +1,188 @@
+use actix_web::error::ErrorInternalServerError;
+use actix_web::{Error, HttpResponse, Responder, State};
+
+use futures::{
+    future::{
+        self,
+        Either::{A, B},
+    },
+    Future, IntoFuture,
+};
+use std::fmt::Display;
+
+use crate::fragment::StatusCounts;
+use crate::intercom::{self, NetworkMsg};
+pub use crate::rest::Context;
+
+macro_rules! ActixFuture {
+    () => { impl Future<Item = impl Responder + 'static, Error = impl Into<Error> + 'static> + 'static }
+}
+
+/// content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub fn get_metrics(context: State<Context>) -> ActixFuture!() {
+    context.try_full_fut().and_then(|context| {
+        let (reply_handle, reply_future) =
+            intercom::unary_reply::<_, intercom::Error>(context.logger.clone());
+        let peer_stats = context
+            .network_task
+            .clone()
+            .try_send(NetworkMsg::PeerStats(reply_handle))
+            .map_err(ErrorInternalServerError)
+            .into_future()
+            .and_then(move |_| reply_future.map_err(ErrorInternalServerError));
+        let status_counts = context
+            .logs
+            .status_counts()
+            .map_err(|()| ErrorInternalServerError("Could not read the fragment logs"));
+        let tip = context
+            .blockchain_tip
+            .get_ref()
+            .map_err(|infallible| match infallible {});
+        let explorer_chain_length = match context.explorer {
+            Some(ref explorer) => A(explorer
+                .db
+                .get_latest_block_chain_length()
+                .map(Some)
+                .map_err(|infallible| match infallible {})),
+            None => B(future::ok(None)),
+        };
+        peer_stats
+            .join4(status_counts, tip, explorer_chain_length)
+            .map(
+                move |(peer_stats, status_counts, tip, explorer_chain_length)| {
+                    let stats = &context.stats_counter;
+                    let tip_chain_length = u32::from(tip.chain_length());
+                    let mut metrics = Metrics::default();
+                    metrics.gauge(
+                        "jormungandr_uptime_seconds",
+                        "Time elapsed since the node started",
+                        stats.uptime_sec(),
+                    );
+                    metrics.counter(
+                        "jormungandr_fragments_received_total",
+                        "Fragments received and accepted in the pool",
+                        stats.tx_recv_cnt(),
+                    );
+                    metrics.counter(
+                        "jormungandr_blocks_received_total",
+                        "Blocks received and accepted in the blockchain",
+                        stats.block_recv_cnt(),
+                    );
+                    metrics.gauge(
+                        "jormungandr_mempool_fragments",
+                        "Fragments pending in the pool",
+                        stats.mempool_size(),
+                    );
+                    metrics.describe(
+                        "jormungandr_fragment_logs",
+                        "gauge",
+                        "Fragment logs by status",
+                    );
+                    for (status, count) in by_status(status_counts) {
+                        metrics.sample("jormungandr_fragment_logs", &[("status", status)], count);
+                    }
+                    metrics.gauge(
+                        "jormungandr_peers",
+                        "Peers in the peer map",
+                        peer_stats.len(),
+                    );
+                    metrics.describe(
+                        "jormungandr_peer_received_bytes_total",
+                        "counter",
+                        "Bytes of the blocks, headers and fragments received from a peer",
+                    );
+                    for (node_id, stats) in &peer_stats {
+                        metrics.sample(
+                            "jormungandr_peer_received_bytes_total",
+                            &[("node_id", &node_id.to_string())],
+                            stats.bytes_received(),
+                        );
+                    }
+                    metrics.describe(
+                        "jormungandr_block_processing_seconds",
+                        "summary",
+                        "Time spent processing the blocks created or received by the node",
+                    );
+                    metrics.sample(
+                        "jormungandr_block_processing_seconds_sum",
+                        &[],
+                        stats.block_process_time().as_micros() as f64 / 1_000_000.0,
+                    );
+                    metrics.sample(
+                        "jormungandr_block_processing_seconds_count",
+                        &[],
+                        stats.block_process_cnt(),
+                    );
+                    metrics.gauge(
+                        "jormungandr_tip_chain_length",
+                        "Chain length of the tip of the blockchain",
+                        tip_chain_length,
+                    );
+                    metrics.counter(
+                        "jormungandr_leader_events_total",
+                        "Leader events scheduled for the leaders of the node",
+                        stats.leader_event_cnt(),
+                    );
+                    metrics.counter(
+                        "jormungandr_leader_blocks_total",
+                        "Blocks created by the leaders of the node",
+                        stats.leader_block_cnt(),
+                    );
+                    if let Some(explorer_chain_length) = explorer_chain_length {
+                        metrics.gauge(
+                            "jormungandr_explorer_indexing_lag_blocks",
+                            "Blocks of the tip not indexed by the explorer yet",
+                            tip_chain_length.saturating_sub(u32::from(explorer_chain_length)),
+                        );
+                    }
+                    HttpResponse::Ok()
+                        .content_type(CONTENT_TYPE)
+                        .body(metrics.0)
+                },
+            )
+    })
+}
+
+fn by_status(counts: StatusCounts) -> Vec<(&'static str, usize)> {
+    vec![
+        ("pending", counts.pending),
+        ("rejected", counts.rejected),
+        ("in_a_block", counts.in_a_block),
+        ("evicted", counts.evicted),
+    ]
+}
+
+/// Metrics rendered in the Prometheus text exposition format
+#[derive(Default)]
+struct Metrics(String);
+
+impl Metrics {
+    fn describe(&mut self, name: &str, kind: &str, help: &str) {
+        self.0.push_str(&format!("# HELP {} {}\n", name, help));
+        self.0.push_str(&format!("# TYPE {} {}\n", name, kind));
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(label, label_value)| format!("{}=\"{}\"", label, label_value))
+                .collect::<Vec<_>>();
+            self.0.push_str(&format!("{{{}}}", labels.join(",")));
+        }
+        self.0.push_str(&format!(" {}\n", value));
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: impl Display) {
+        self.describe(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: impl Display) {
+        self.describe(name, "counter", help);
+        self.sample(name, &[], value);
+    }
+}
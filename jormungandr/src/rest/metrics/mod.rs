@@ -0,0 +1,10 @@
+mod handlers;
+
+use actix_web::dev::Resource;
+
+pub fn resources() -> Vec<(
+    &'static str,
+    &'static dyn Fn(&mut Resource<handlers::Context>),
+)> {
+    vec![("/metrics", &|r| r.get().with_async(handlers::get_metrics))]
+}
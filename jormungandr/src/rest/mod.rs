@@ -3,6 +3,7 @@
 mod server;
 
 pub mod explorer;
+pub mod metrics;
 pub mod v0;
 
 pub use self::server::{Error, Server};
@@ -18,6 +19,7 @@ use std::sync::{Arc, RwLock};
 use crate::blockchain::{Blockchain, Tip};
 use crate::fragment::Logs;
 use crate::leadership::Logs as LeadershipLogs;
+use crate::network::BootstrapProgress;
 use crate::secure::enclave::Enclave;
 use crate::settings::start::{Cors as CorsConfig, Error as ConfigError, Rest};
 use crate::stats_counter::StatsCounter;
@@ -30,6 +32,7 @@ pub struct Context {
     full: Arc<RwLock<Option<Arc<FullContext>>>>,
     server: Arc<RwLock<Option<Arc<Server>>>>,
     node_state: Arc<RwLock<NodeState>>,
+    bootstrap_progress: BootstrapProgress,
 }
 
 impl Context {
@@ -38,6 +41,7 @@ impl Context {
             full: Default::default(),
             server: Default::default(),
             node_state: Arc::new(RwLock::new(NodeState::StartingRestServer)),
+            bootstrap_progress: BootstrapProgress::default(),
         }
     }
 
@@ -82,6 +86,10 @@ impl Context {
             .expect("Context node state poisoned")
             .clone()
     }
+
+    pub fn bootstrap_progress(&self) -> &BootstrapProgress {
+        &self.bootstrap_progress
+    }
 }
 
 #[derive(Clone)]
@@ -132,6 +140,15 @@ pub fn start_rest_server(
             ))
         }
 
+        // served at `/metrics`, the default path scraped by Prometheus; the
+        // root prefix matches any path, it must be the last application
+        apps.push(build_app(
+            app_context.clone(),
+            "/",
+            metrics::resources(),
+            &cors_cfg,
+        ));
+
         apps
     })?;
     context.set_server(server);
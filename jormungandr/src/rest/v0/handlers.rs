@@ -2,6 +2,7 @@ use jormungandr_lib::interfaces::*;
 use jormungandr_lib::time::SystemTime;
 
 use actix_web::error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound};
+use actix_web::http::header::CACHE_CONTROL;
 use actix_web::{Error, HttpResponse};
 use actix_web::{Json, Path, Query, Responder, State};
 use chain_core::property::{Block, Deserialize, Serialize as _};
@@ -12,8 +13,10 @@ use chain_impl_mockchain::key::Hash;
 use chain_impl_mockchain::leadership::{Leader, LeadershipConsensus};
 use chain_impl_mockchain::value::{Value, ValueError};
 
+use crate::blockcfg::Header;
 use crate::blockchain::Ref;
 use crate::intercom::{self, NetworkMsg, TransactionMsg};
+use crate::network::BootstrapProgressReport;
 use crate::secure::NodeSecret;
 use bytes::{Bytes, IntoBuf};
 use futures::{
@@ -21,10 +24,12 @@ use futures::{
         self,
         Either::{A, B},
     },
-    Future, IntoFuture, Stream,
+    stream, Future, IntoFuture, Stream,
 };
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::timer::Interval;
 
 pub use crate::rest::{Context, FullContext, NodeState};
 
@@ -76,35 +81,217 @@ fn parse_account_id(id_hex: &str) -> Result<Identifier, Error> {
         .map_err(|e| ErrorBadRequest(e))
 }
 
-pub fn get_message_logs(context: State<Context>) -> ActixFuture!() {
-    context.try_full_fut().and_then(|context| {
-        context
-            .logs
-            .logs()
-            .map_err(|_| ErrorInternalServerError("Failed to get logs"))
-            .map(Json)
+#[derive(Deserialize)]
+pub struct MessageLogsQuery {
+    status: Option<FragmentStatusFilter>,
+    origin: Option<FragmentOriginFilter>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FragmentStatusFilter {
+    Pending,
+    Rejected,
+    InABlock,
+    Evicted,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FragmentOriginFilter {
+    Network,
+    Rest,
+}
+
+impl MessageLogsQuery {
+    fn is_unfiltered(&self) -> bool {
+        self.status.is_none() && self.origin.is_none()
+    }
+
+    fn matches(&self, log: &FragmentLog) -> bool {
+        let status_matches = match self.status {
+            None => true,
+            Some(FragmentStatusFilter::Pending) => log.is_pending(),
+            Some(FragmentStatusFilter::Rejected) => log.is_rejected(),
+            Some(FragmentStatusFilter::InABlock) => log.is_in_a_block(),
+            Some(FragmentStatusFilter::Evicted) => log.is_evicted(),
+        };
+        let origin_matches = match self.origin {
+            None => true,
+            Some(FragmentOriginFilter::Network) => log.received_from().is_network(),
+            Some(FragmentOriginFilter::Rest) => log.received_from().is_rest(),
+        };
+        status_matches && origin_matches
+    }
+}
+
+pub fn get_message_logs(context: State<Context>, query: Query<MessageLogsQuery>) -> ActixFuture!() {
+    context.try_full_fut().and_then(move |context| {
+        let query = query.into_inner();
+        if query.is_unfiltered() {
+            A(context.logs.logs())
+        } else {
+            B(context.logs.logs_filtered(move |log| query.matches(log)))
+        }
+        .map_err(|_| ErrorInternalServerError("Failed to get logs"))
+        .map(Json)
     })
 }
 
-pub fn post_message(context: State<Context>, message: Bytes) -> Result<impl Responder, Error> {
-    let fragment = Fragment::deserialize(message.into_buf()).map_err(ErrorBadRequest)?;
-    let msg = TransactionMsg::SendTransaction(FragmentOrigin::Rest, vec![fragment]);
+pub fn get_fragment_log(context: State<Context>, fragment_id_hex: Path<String>) -> ActixFuture!() {
     context
-        .try_full()?
-        .transaction_task
-        .clone()
-        .try_send(msg)
-        .map_err(|e| ErrorInternalServerError(e))?;
-    Ok(HttpResponse::Ok().finish())
+        .try_full()
+        .and_then(|context| {
+            parse_fragment_id(&fragment_id_hex).map(|fragment_id| (context, fragment_id))
+        })
+        .into_future()
+        .and_then(|(context, fragment_id)| {
+            context
+                .logs
+                .get(fragment_id)
+                .map_err(|_| ErrorInternalServerError("Failed to get logs"))
+        })
+        .and_then(|log| log.ok_or_else(|| ErrorNotFound("Fragment not found")))
+        .map(Json)
+}
+
+pub fn post_fragment_statuses(
+    context: State<Context>,
+    fragment_ids_hex: Json<Vec<String>>,
+) -> ActixFuture!() {
+    context
+        .try_full()
+        .and_then(|context| {
+            fragment_ids_hex
+                .iter()
+                .map(|fragment_id_hex| parse_fragment_id(fragment_id_hex))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|fragment_ids| (context, fragment_ids))
+        })
+        .into_future()
+        .and_then(|(context, fragment_ids)| {
+            context
+                .logs
+                .get_all(fragment_ids)
+                .map_err(|_| ErrorInternalServerError("Failed to get logs"))
+        })
+        .map(Json)
+}
+
+fn parse_fragment_id(hex: &str) -> Result<Hash, Error> {
+    Blake2b256::from_str(hex)
+        .map_err(|e| ErrorBadRequest(e))
+        .map(Into::into)
+}
+
+#[derive(Deserialize)]
+pub struct PostMessageQuery {
+    /// wait for the fragment to be checked by the pool and reply with the outcome
+    #[serde(default)]
+    wait: bool,
+}
+
+pub fn post_message(
+    context: State<Context>,
+    message: Bytes,
+    query: Query<PostMessageQuery>,
+) -> ActixFuture!() {
+    Fragment::deserialize(message.into_buf())
+        .map_err(ErrorBadRequest)
+        .and_then(|fragment| context.try_full().map(|context| (context, fragment)))
+        .into_future()
+        .and_then(move |(context, fragment)| {
+            let mut transaction_task = context.transaction_task.clone();
+            if query.wait {
+                let (reply_handle, reply_future) =
+                    intercom::unary_reply::<_, intercom::Error>(context.logger.clone());
+                let msg = TransactionMsg::SubmitTransaction(
+                    FragmentOrigin::Rest,
+                    vec![fragment],
+                    reply_handle,
+                );
+                A(transaction_task
+                    .try_send(msg)
+                    .map_err(ErrorInternalServerError)
+                    .into_future()
+                    .and_then(move |_| reply_future.map_err(ErrorInternalServerError))
+                    .and_then(|submissions| {
+                        submissions.into_iter().next().ok_or_else(|| {
+                            ErrorInternalServerError("No submission outcome for the fragment")
+                        })
+                    })
+                    .map(|submission| HttpResponse::Ok().json(submission)))
+            } else {
+                let msg = TransactionMsg::SendTransaction(FragmentOrigin::Rest, vec![fragment]);
+                B(transaction_task
+                    .try_send(msg)
+                    .map_err(ErrorInternalServerError)
+                    .map(|_| HttpResponse::Ok().finish())
+                    .into_future())
+            }
+        })
 }
 
 pub fn get_tip(context: State<Context>) -> ActixFuture!() {
     chain_tip_fut(&context).map(|tip| tip.hash().to_string())
 }
 
+/// interval of the comments sent to keep idle event streams open
+const EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+pub fn get_events(context: State<Context>) -> Result<impl Responder, Error> {
+    let context = context.try_full()?;
+    let tips = context
+        .blockchain_tip
+        .subscribe()
+        .map(|header| server_sent_event("tip", &header_json(&header)));
+    let blocks = context
+        .blockchain
+        .subscribe_to_new_blocks()
+        .map(|header| server_sent_event("block", &header_json(&header)));
+    let fragments = context
+        .logs
+        .subscribe()
+        .map(|log| server_sent_event("fragment", &log));
+    let keep_alive = Interval::new_interval(EVENTS_KEEP_ALIVE)
+        .map(|_| Some(Bytes::from_static(b":\n\n")))
+        .map_err(|_| ());
+    // the subscriptions end when the subscriber lags behind, the whole stream
+    // is then ended so that the client knows to reconnect
+    let events = tips
+        .map(Some)
+        .chain(stream::once(Ok(None)))
+        .select(blocks.map(Some).chain(stream::once(Ok(None))))
+        .select(fragments.map(Some).chain(stream::once(Ok(None))))
+        .select(keep_alive)
+        .take_while(|event| Ok(event.is_some()))
+        .filter_map(|event| event)
+        .map_err(|()| ErrorInternalServerError("Event subscription failed"));
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .streaming(events))
+}
+
+fn header_json(header: &Header) -> serde_json::Value {
+    json!({
+        "hash": header.hash().to_string(),
+        "parentHash": header.block_parent_hash().to_string(),
+        "chainLength": header.chain_length().to_string(),
+        "date": header.block_date().to_string(),
+    })
+}
+
+fn server_sent_event(event: &str, data: &impl serde::Serialize) -> Bytes {
+    let data = serde_json::to_string(data).expect("event data serialization cannot fail");
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
 #[derive(Serialize)]
 struct NodeStatsDto {
     state: NodeState,
+    #[serde(rename = "bootstrapProgress", skip_serializing_if = "Option::is_none")]
+    bootstrap_progress: Option<BootstrapProgressReport>,
     #[serde(flatten)]
     stats: Option<serde_json::Value>,
 }
@@ -176,8 +363,14 @@ pub fn get_stats_counter(context: State<Context>) -> ActixFuture!() {
         Err(_) => B(future::ok(None)),
     }
     .map(move |stats| {
+        let state = context.node_state();
+        let bootstrap_progress = match state {
+            NodeState::Bootstrapping => context.bootstrap_progress().report(),
+            _ => None,
+        };
         Json(NodeStatsDto {
-            state: context.node_state(),
+            state,
+            bootstrap_progress,
             stats,
         })
     })
@@ -394,3 +587,32 @@ pub fn get_network_stats(context: State<Context>) -> ActixFuture!() {
             })
     })
 }
+
+pub fn get_network_reputation(context: State<Context>) -> ActixFuture!() {
+    context.try_full_fut().and_then(|context| {
+        let (reply_handle, reply_future) =
+            intercom::unary_reply::<_, intercom::Error>(context.logger.clone());
+        context
+            .network_task
+            .clone()
+            .try_send(NetworkMsg::PeerReputations(reply_handle))
+            .map_err(ErrorInternalServerError)
+            .into_future()
+            .and_then(move |_| reply_future.map_err(ErrorInternalServerError))
+            .map(|reputations| {
+                let reputations = reputations
+                    .into_iter()
+                    .map(|(node_id, reputation)| {
+                        json! ({
+                            "nodeId": node_id.to_string(),
+                            "penalty": reputation.penalty(),
+                            "invalidBlocks": reputation.invalid_blocks(),
+                            "invalidFragments": reputation.invalid_fragments(),
+                            "bannedUntil": reputation.banned_until().map(SystemTime::from),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Json(reputations)
+            })
+    })
+}
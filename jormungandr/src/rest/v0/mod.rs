@@ -16,9 +16,16 @@ pub fn resources() -> Vec<(
         ("/block/{block_id}/next_id", &|r| {
             r.get().with_async(handlers::get_block_next_id)
         }),
+        ("/events", &|r| r.get().with(handlers::get_events)),
         ("/fragment/logs", &|r| {
             r.get().with_async(handlers::get_message_logs)
         }),
+        ("/fragment/statuses", &|r| {
+            r.post().with_async(handlers::post_fragment_statuses)
+        }),
+        ("/fragment/{fragment_id}", &|r| {
+            r.get().with_async(handlers::get_fragment_log)
+        }),
         ("/leaders", &|r| {
             r.get().with(handlers::get_leaders);
             r.post().with(handlers::post_leaders);
@@ -29,6 +36,9 @@ pub fn resources() -> Vec<(
         ("/leaders/{leader_id}", &|r| {
             r.delete().with(handlers::delete_leaders)
         }),
+        ("/network/reputation", &|r| {
+            r.get().with_async(handlers::get_network_reputation)
+        }),
         ("/network/stats", &|r| {
             r.get().with_async(handlers::get_network_stats)
         }),
@@ -40,7 +50,7 @@ pub fn resources() -> Vec<(
             r.get().with_async(handlers::get_stake_pools)
         }),
         ("/shutdown", &|r| r.get().with(handlers::get_shutdown)),
-        ("/message", &|r| r.post().with(handlers::post_message)),
+        ("/message", &|r| r.post().with_async(handlers::post_message)),
         ("/node/stats", &|r| {
             r.get().with_async(handlers::get_stats_counter)
         }),
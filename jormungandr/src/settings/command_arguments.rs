@@ -94,6 +94,57 @@ pub struct CommandLine {
     /// this option is useful for scripting retrieving the logs of the version of this application.
     #[structopt(long = "source-version")]
     pub source_version: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Command {
+    /// Offline operations on the blockchain storage (the node is not started)
+    Storage(StorageCommand),
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub enum StorageCommand {
+    /// Replay the blocks of the storage from the block0 to the `HEAD` tag,
+    /// checking the headers and applying the blocks to the ledger, and report
+    /// the first inconsistency found
+    Verify,
+    /// Verify the storage, then point the `HEAD` tag to the last valid block
+    Repair,
+    /// Write blocks of the storage in a file, each block being prefixed
+    /// with its size as a 32 bits big endian integer
+    Export {
+        /// hash of the block after which to start the export. If not set,
+        /// defaults to the block0
+        #[structopt(long = "from", parse(try_from_str))]
+        from: Option<HeaderHash>,
+        /// hash of the last block to export. If not set, defaults to the
+        /// `HEAD` tag
+        #[structopt(long = "to", parse(try_from_str))]
+        to: Option<HeaderHash>,
+        /// the file to write the blocks into
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Validate and store the blocks of a file written by `export`; the
+    /// first block of the file has to be the child of the `HEAD` tag
+    Import {
+        /// the file to read the blocks from
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+    },
+    /// Copy the blocks of an SQLite storage, from the block0 to the `HEAD`
+    /// tag, into the configured storage. The other branches and tags are
+    /// not copied
+    Migrate {
+        /// the storage directory containing the `blocks.sqlite` file
+        #[structopt(parse(from_os_str))]
+        from: PathBuf,
+    },
 }
 
 impl CommandLine {
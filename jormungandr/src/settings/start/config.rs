@@ -18,7 +18,12 @@ use std::{collections::BTreeMap, fmt, net::SocketAddr, path::PathBuf};
 pub struct Config {
     #[serde(default)]
     pub secret_files: Vec<PathBuf>,
-    pub storage: Option<PathBuf>,
+    pub storage: Option<Storage>,
+
+    /// how much of the blockchain history is served to the other nodes
+    #[serde(default)]
+    pub served_blocks: ServedBlocks,
+
     pub log: Option<ConfigLogSettings>,
 
     /// setting of the mempool, fragment logs and related data
@@ -113,6 +118,51 @@ pub struct Leadership {
     pub log_ttl: Duration,
     /// interval between 2 garbage collection check logs
     pub garbage_collection_interval: Duration,
+    /// the algorithm used to select the fragments of the pool to put in a new block
+    #[serde(default)]
+    pub fragment_selection: FragmentSelection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FragmentSelection {
+    /// select the fragments in the order they have been received
+    OldestFirst,
+    /// select the fragments paying the highest fee per byte first
+    FeePriority,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServedBlocks {
+    /// serve every block since block0
+    All,
+    /// only serve block0 and the blocks of the last `epochs` epochs, the
+    /// blocks which can still be rolled back are always served. All the
+    /// blocks are kept in the storage.
+    Recent { epochs: u32 },
+}
+
+/// the storage directory, either its path alone or along with the database
+/// used to store the blocks in it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Storage {
+    Path(PathBuf),
+    Settings {
+        path: PathBuf,
+        #[serde(default)]
+        backend: StorageBackend,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// the blocks are stored in an SQLite database
+    Sqlite,
+    /// the blocks are stored in a sled embedded key-value database
+    Sled,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -126,6 +176,24 @@ pub struct Mempool {
     pub log_ttl: Duration,
     /// interval between 2 garbage collection check of the mempool and the log cache.
     pub garbage_collection_interval: Duration,
+    /// maximum number of fragments pending in the mempool. Unbounded if not set.
+    pub max_entries: Option<usize>,
+    /// maximum total size, in bytes, of the fragments pending in the mempool.
+    /// Unbounded if not set.
+    pub max_bytes: Option<usize>,
+    /// which fragments to evict from the mempool when one of the limits is reached
+    #[serde(default)]
+    pub eviction_policy: EvictionPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// evict the fragments paying the lowest fee per byte first, the oldest
+    /// fragments first between the ones paying the same fee per byte
+    LowestFee,
+    /// evict the fragments in the order they have been received
+    OldestFirst,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -162,19 +230,63 @@ impl Default for Mempool {
             fragment_ttl: Duration::new(30 * 60, 0),
             log_ttl: Duration::new(3600, 0),
             garbage_collection_interval: Duration::new(3600 / 4, 0),
+            max_entries: None,
+            max_bytes: None,
+            eviction_policy: EvictionPolicy::default(),
         }
     }
 }
 
+impl Default for EvictionPolicy {
+    fn default() -> Self {
+        EvictionPolicy::LowestFee
+    }
+}
+
 impl Default for Leadership {
     fn default() -> Self {
         Leadership {
             log_ttl: Duration::new(3600, 0),
             garbage_collection_interval: Duration::new(3600 / 4, 0),
+            fragment_selection: FragmentSelection::default(),
         }
     }
 }
 
+impl Default for StorageBackend {
+    fn default() -> Self {
+        StorageBackend::Sqlite
+    }
+}
+
+impl Storage {
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Storage::Path(path) => path,
+            Storage::Settings { path, .. } => path,
+        }
+    }
+
+    pub fn backend(&self) -> StorageBackend {
+        match self {
+            Storage::Path(_) => StorageBackend::default(),
+            Storage::Settings { backend, .. } => *backend,
+        }
+    }
+}
+
+impl Default for ServedBlocks {
+    fn default() -> Self {
+        ServedBlocks::All
+    }
+}
+
+impl Default for FragmentSelection {
+    fn default() -> Self {
+        FragmentSelection::OldestFirst
+    }
+}
+
 impl std::str::FromStr for TrustedPeer {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
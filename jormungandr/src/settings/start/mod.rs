@@ -1,7 +1,7 @@
 pub mod config;
 pub mod network;
 
-use self::config::{Config, Leadership, Mempool};
+use self::config::{Config, Leadership, Mempool, ServedBlocks, StorageBackend};
 pub use self::config::{Cors, Rest};
 use self::network::Protocol;
 use crate::rest::Error as RestError;
@@ -25,6 +25,8 @@ custom_error! {pub Error
 pub struct Settings {
     pub network: network::Configuration,
     pub storage: Option<PathBuf>,
+    pub served_blocks: ServedBlocks,
+    pub storage_backend: StorageBackend,
     pub block_0: Block0Info,
     pub secrets: Vec<PathBuf>,
     pub rest: Option<Rest>,
@@ -115,7 +117,10 @@ impl RawSettings {
 
         let storage = match (
             command_arguments.storage.as_ref(),
-            config.as_ref().map_or(None, |cfg| cfg.storage.as_ref()),
+            config
+                .as_ref()
+                .and_then(|cfg| cfg.storage.as_ref())
+                .map(|storage| storage.path()),
         ) {
             (Some(path), _) => Some(path.clone()),
             (None, Some(path)) => Some(path.clone()),
@@ -153,6 +158,13 @@ impl RawSettings {
 
         Ok(Settings {
             storage,
+            served_blocks: config
+                .as_ref()
+                .map_or(ServedBlocks::default(), |cfg| cfg.served_blocks),
+            storage_backend: config
+                .as_ref()
+                .and_then(|cfg| cfg.storage.as_ref())
+                .map_or(StorageBackend::default(), |storage| storage.backend()),
             block_0,
             network,
             secrets,
@@ -0,0 +1,144 @@
+//! Flat file format to export and import blocks.
+//!
+//! The file is the sequence of the serialized blocks, each block being
+//! prefixed with its size in bytes as a 32 bits big endian integer.
+
+use crate::blockcfg::Block;
+use chain_core::property::{Deserialize as _, Serialize as _};
+use std::io::{self, Read, Write};
+
+/// Size of the largest block accepted in a file. The contents of the
+/// blocks are bounded by the `block_content_max_size` setting of the
+/// blockchain, far below this; block0, which carries the initial funds and
+/// settings, is not, hence the margin.
+const MAX_BLOCK_SIZE: usize = 32 * 1024 * 1024;
+
+pub struct BlockWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> BlockWriter<W> {
+    pub fn new(inner: W) -> Self {
+        BlockWriter { inner }
+    }
+
+    pub fn write_block(&mut self, block: &Block) -> io::Result<()> {
+        let bytes = block.serialize_as_vec()?;
+        let size = bytes.len() as u32;
+        self.inner.write_all(&size.to_be_bytes())?;
+        self.inner.write_all(&bytes)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Iterates over the blocks of a file, in the order they were written.
+pub struct BlockReader<R> {
+    inner: R,
+}
+
+impl<R: Read> BlockReader<R> {
+    pub fn new(inner: R) -> Self {
+        BlockReader { inner }
+    }
+
+    fn read_block(&mut self) -> io::Result<Option<Block>> {
+        let mut size = [0; 4];
+        let mut read = 0;
+        while read < size.len() {
+            match self.inner.read(&mut size[read..])? {
+                0 if read == 0 => return Ok(None),
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                n => read += n,
+            }
+        }
+        // the size is checked before allocating, a corrupted size must not
+        // make the node allocate gigabytes
+        let size = u32::from_be_bytes(size) as usize;
+        if size > MAX_BLOCK_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "block of {} bytes, larger than the maximum of {} bytes",
+                    size, MAX_BLOCK_SIZE
+                ),
+            ));
+        }
+        let mut bytes = vec![0; size];
+        self.inner.read_exact(&mut bytes)?;
+        Block::deserialize(bytes.as_slice()).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for BlockReader<R> {
+    type Item = io::Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_block().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockReader, BlockWriter, MAX_BLOCK_SIZE};
+    use crate::{blockcfg::Block, blockchain::testing::ChainBuilder};
+    use std::io;
+
+    fn write_blocks(chain: &ChainBuilder) -> (Vec<u8>, Vec<Block>) {
+        let mut blocks = vec![chain.block0().clone()];
+        blocks.extend(chain.branch(&chain.block0().header, 1..=10));
+        let mut writer = BlockWriter::new(Vec::new());
+        for block in &blocks {
+            writer.write_block(block).unwrap();
+        }
+        writer.flush().unwrap();
+        (writer.inner, blocks)
+    }
+
+    #[test]
+    fn blocks_are_read_as_written() {
+        let chain = ChainBuilder::new(100);
+        let (file, blocks) = write_blocks(&chain);
+
+        let read = BlockReader::new(file.as_slice())
+            .map(|block| block.unwrap().header.hash())
+            .collect::<Vec<_>>();
+        let written = blocks
+            .iter()
+            .map(|block| block.header.hash())
+            .collect::<Vec<_>>();
+        assert_eq!(read, written);
+    }
+
+    #[test]
+    fn empty_file_has_no_blocks() {
+        assert!(BlockReader::new(io::empty()).next().is_none());
+    }
+
+    #[test]
+    fn truncated_file_fails() {
+        let chain = ChainBuilder::new(100);
+        let (file, _) = write_blocks(&chain);
+
+        // in the middle of the size of the second block and of its bytes
+        let first_block_size = 4 + u32::from_be_bytes([file[0], file[1], file[2], file[3]]);
+        for end in &[first_block_size as usize + 2, file.len() - 1] {
+            let mut reader = BlockReader::new(&file[..*end]);
+            assert!(reader.next().unwrap().is_ok());
+            let last = reader.last().unwrap();
+            assert_eq!(last.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn oversized_block_fails() {
+        let mut file = (MAX_BLOCK_SIZE as u32 + 1).to_be_bytes().to_vec();
+        file.extend_from_slice(&[0; 16]);
+
+        let mut reader = BlockReader::new(file.as_slice());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -7,7 +7,8 @@ use std::io;
 
 custom_error! {pub ErrorKind
    SQLite = "SQLite file",
-   Block0 = "Block0"
+   Block0 = "Block0",
+   BlockFile = "blocks file"
 }
 
 custom_error! {pub Error
@@ -23,6 +24,8 @@ custom_error! {pub Error
     NodeSecrets { source: secure::NodeSecretFromFileError} = "Error while loading the node's secrets.",
     Block0InFuture = "Block 0 is set to start in the future",
     ExplorerBootstrapError { source: explorer::error::Error } = "Error while loading the explorer from storage",
+    ImportBlocks { source: network::BootstrapError, reason: ErrorKind } = "Error while importing the {reason}",
+    InconsistentStorage = "The storage is inconsistent, use `storage repair' to reset the HEAD tag to the last valid block",
 }
 
 impl Error {
@@ -41,6 +44,8 @@ impl Error {
             Error::FetchBlock0 { .. } => 9,
             Error::NetworkBootstrapError { .. } => 10,
             Error::ExplorerBootstrapError { .. } => 11,
+            Error::InconsistentStorage => 12,
+            Error::ImportBlocks { .. } => 13,
         }
     }
 }
@@ -1,17 +1,35 @@
+mod block_file;
 mod error;
+mod sled_store;
 
 pub use self::error::{Error, ErrorKind};
+use self::sled_store::SledBlockStore;
 use crate::{
-    blockcfg::{Block, Leadership},
-    blockchain::{Blockchain, Branch, ErrorKind as BlockchainError, Tip},
+    blockcfg::{Block, HeaderHash, Leadership},
+    blockchain::{
+        self, Blockchain, Branch, ErrorKind as BlockchainError, Storage, Tip, MAIN_BRANCH_TAG,
+    },
     leadership::NewEpochToSchedule,
     network,
-    settings::start::Settings,
+    settings::start::{
+        config::{ServedBlocks, StorageBackend},
+        Settings,
+    },
+};
+use chain_storage::{
+    error::Error as StorageError,
+    memory::MemoryBlockStore,
+    store::{self, BlockStore},
 };
-use chain_storage::{memory::MemoryBlockStore, store::BlockStore};
 use chain_storage_sqlite::SQLiteBlockStore;
 use slog::Logger;
-use std::{sync::Arc, time::Duration};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::mpsc;
 
 pub type NodeStorage = Box<dyn BlockStore<Block = Block> + Send + Sync>;
@@ -29,14 +47,75 @@ pub fn prepare_storage(setting: &Settings, logger: &Logger) -> Result<NodeStorag
                 source: err,
                 reason: ErrorKind::SQLite,
             })?;
-            let mut sqlite = dir.clone();
-            sqlite.push("blocks.sqlite");
-            info!(logger, "storing blockchain in '{:?}'", sqlite);
-            Ok(Box::new(SQLiteBlockStore::new(sqlite)))
+            match setting.storage_backend {
+                StorageBackend::Sqlite => {
+                    let mut sqlite = dir.clone();
+                    sqlite.push("blocks.sqlite");
+                    info!(logger, "storing blockchain in '{:?}'", sqlite);
+                    Ok(Box::new(SQLiteBlockStore::new(sqlite)))
+                }
+                StorageBackend::Sled => {
+                    let mut sled = dir.clone();
+                    sled.push("blocks.sled");
+                    info!(logger, "storing blockchain in '{:?}'", sled);
+                    Ok(Box::new(SledBlockStore::open(sled)?))
+                }
+            }
         }
     }
 }
 
+/// copy the blocks of the SQLite storage of the directory `sqlite_dir`,
+/// from the block0 to the `HEAD` tag, into `storage`
+///
+/// The block store can neither list its blocks nor its tags: the blocks of
+/// the other branches and the tags other than `HEAD` are not copied. The
+/// blocks already in `storage` are skipped, so an interrupted migration can
+/// be run again.
+pub fn migrate_storage(
+    settings: &Settings,
+    mut storage: NodeStorage,
+    sqlite_dir: &Path,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let sqlite = sqlite_dir.join("blocks.sqlite");
+    if !sqlite.is_file() {
+        return Err(Error::IO {
+            source: io::Error::new(io::ErrorKind::NotFound, format!("{:?}", sqlite)),
+            reason: ErrorKind::SQLite,
+        });
+    }
+    let source: NodeStorage = Box::new(SQLiteBlockStore::new(sqlite));
+
+    let block0 = prepare_block_0(settings, &source, logger)?;
+    let block0_hash = block0.header.hash();
+    let head = source.get_tag(MAIN_BRANCH_TAG)?.ok_or_else(|| {
+        blockchain::Error::from(BlockchainError::NoTag(MAIN_BRANCH_TAG.to_owned()))
+    })?;
+
+    info!(logger, "migrating the storage"; "head" => %head);
+    warn!(
+        logger,
+        "only the main branch and the {} tag are migrated, the other branches and tags are not",
+        MAIN_BRANCH_TAG
+    );
+    let mut put_block = |block: &Block| match storage.put_block(block) {
+        Ok(()) | Err(StorageError::BlockAlreadyPresent) => Ok(()),
+        Err(err) => Err(err),
+    };
+    put_block(&block0)?;
+    let mut count = 1u64;
+    for info in store::iterate_range(&source, &block0_hash, &head)? {
+        let (block, _) = source.get_block(&info?.block_hash)?;
+        put_block(&block)?;
+        count += 1;
+    }
+    storage.put_tag(MAIN_BRANCH_TAG, &head)?;
+
+    info!(logger, "storage migrated"; "head" => %head, "count" => count);
+    Ok(())
+}
+
 /// loading the block 0 is not as trivial as it seems,
 /// there are different cases that we may encounter:
 ///
@@ -88,19 +167,14 @@ pub fn load_blockchain(
     storage: NodeStorage,
     epoch_event: mpsc::Sender<NewEpochToSchedule>,
     block_cache_ttl: Duration,
+    served_blocks: ServedBlocks,
 ) -> Result<(Blockchain, Tip), Error> {
     use tokio::prelude::*;
 
-    let mut blockchain = Blockchain::new(storage, block_cache_ttl);
+    let mut blockchain = Blockchain::new(storage, block_cache_ttl, served_blocks);
     let mut blockchain_clone = blockchain.clone();
 
-    let main_branch: Branch = match blockchain.load_from_block0(block0.clone()).wait() {
-        Err(error) => match error.kind() {
-            BlockchainError::Block0AlreadyInStorage => blockchain.load_from_storage(block0).wait(),
-            _ => Err(error),
-        },
-        Ok(branch) => Ok(branch),
-    }?;
+    let main_branch = load_main_branch(&mut blockchain, block0)?;
 
     main_branch
         .get_ref()
@@ -134,3 +208,164 @@ pub fn load_blockchain(
 
     Ok((blockchain, Tip::new(main_branch)))
 }
+
+/// initialize the storage with the block0 or, if the storage already
+/// contains it, load the blocks up to the `HEAD` tag
+fn load_main_branch(blockchain: &mut Blockchain, block0: Block) -> Result<Branch, Error> {
+    use tokio::prelude::*;
+
+    let main_branch = match blockchain.load_from_block0(block0.clone()).wait() {
+        Err(error) => match error.kind() {
+            BlockchainError::Block0AlreadyInStorage => blockchain.load_from_storage(block0).wait(),
+            _ => Err(error),
+        },
+        Ok(branch) => Ok(branch),
+    }?;
+    Ok(main_branch)
+}
+
+/// replay the blocks of the storage from the block0 to the `HEAD` tag and
+/// report the first inconsistency found. With `repair`, the `HEAD` tag is
+/// then pointed to the last valid block.
+pub fn check_storage(
+    block0: Block,
+    storage: NodeStorage,
+    block_cache_ttl: Duration,
+    repair: bool,
+    logger: &Logger,
+) -> Result<(), Error> {
+    use error_chain::ChainedError as _;
+    use tokio::prelude::*;
+
+    let mut blockchain = Blockchain::new(storage, block_cache_ttl, ServedBlocks::All);
+
+    info!(logger, "verifying the storage");
+    let verification = blockchain.verify_storage(block0).wait()?;
+    let last_valid = verification.last_valid.hash();
+    let last_valid_chain_length = u32::from(verification.last_valid.chain_length());
+
+    let error = match verification.error {
+        None => {
+            info!(
+                logger,
+                "the storage is consistent";
+                "head" => %last_valid,
+                "chain_length" => last_valid_chain_length,
+            );
+            return Ok(());
+        }
+        Some(error) => error,
+    };
+
+    error!(
+        logger,
+        "the storage is inconsistent";
+        "head" => ?verification.head.map(|head| head.to_string()),
+        "last_valid_block" => %last_valid,
+        "last_valid_chain_length" => last_valid_chain_length,
+        "reason" => %error.display_chain(),
+    );
+
+    if !repair {
+        return Err(Error::InconsistentStorage);
+    }
+
+    blockchain
+        .storage()
+        .clone()
+        .put_tag(MAIN_BRANCH_TAG.to_owned(), last_valid.clone())
+        .wait()?;
+    warn!(
+        logger,
+        "the HEAD tag now points to the last valid block";
+        "head" => %last_valid,
+        "chain_length" => last_valid_chain_length,
+    );
+    Ok(())
+}
+
+/// write the blocks of the storage following `from` (the block0 by
+/// default) up to `to` (the `HEAD` tag by default) in a blocks file
+pub fn export_blocks(
+    block0: &Block,
+    storage: NodeStorage,
+    from: Option<HeaderHash>,
+    to: Option<HeaderHash>,
+    output: &Path,
+    logger: &Logger,
+) -> Result<(), Error> {
+    use tokio::prelude::*;
+
+    let storage = Storage::new(storage);
+    let from = from.unwrap_or_else(|| block0.header.hash());
+    let to = match to {
+        Some(to) => to,
+        None => storage
+            .get_tag(MAIN_BRANCH_TAG.to_owned())
+            .wait()?
+            .ok_or_else(|| {
+                blockchain::Error::from(BlockchainError::NoTag(MAIN_BRANCH_TAG.to_owned()))
+            })?,
+    };
+    let blocks = storage
+        .stream_from_to(from.clone(), to.clone())
+        .wait()?
+        .ok_or_else(|| {
+            blockchain::Error::from(format!("block {} is not an ancestor of block {}", from, to))
+        })?;
+
+    let file = File::create(output).map_err(|err| Error::IO {
+        source: err,
+        reason: ErrorKind::BlockFile,
+    })?;
+    let mut writer = block_file::BlockWriter::new(BufWriter::new(file));
+    let mut count = 0u64;
+    for block in blocks.wait() {
+        writer.write_block(&block?).map_err(|err| Error::IO {
+            source: err,
+            reason: ErrorKind::BlockFile,
+        })?;
+        count += 1;
+    }
+    writer.flush().map_err(|err| Error::IO {
+        source: err,
+        reason: ErrorKind::BlockFile,
+    })?;
+
+    info!(logger, "blocks exported"; "from" => %from, "to" => %to, "count" => count);
+    Ok(())
+}
+
+/// validate and store the blocks of a blocks file, the first block of the
+/// file having to be the child of the current `HEAD` of the storage
+pub fn import_blocks(
+    block0: Block,
+    storage: NodeStorage,
+    block_cache_ttl: Duration,
+    served_blocks: ServedBlocks,
+    input: &Path,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let mut blockchain = Blockchain::new(storage, block_cache_ttl, served_blocks);
+    let tip = Tip::new(load_main_branch(&mut blockchain, block0)?);
+
+    let file = File::open(input).map_err(|err| Error::IO {
+        source: err,
+        reason: ErrorKind::BlockFile,
+    })?;
+    let blocks = block_file::BlockReader::new(BufReader::new(file));
+    let tip = network::bootstrap::import_blocks(blocks, blockchain, tip, logger.clone()).map_err(
+        |err| Error::ImportBlocks {
+            source: err,
+            reason: ErrorKind::BlockFile,
+        },
+    )?;
+
+    info!(
+        logger,
+        "blocks imported";
+        "tip" => %tip.hash(),
+        "chain_length" => u32::from(tip.chain_length()),
+    );
+    Ok(())
+}
@@ -0,0 +1,248 @@
+//! Block storage backed by the embedded key-value database sled.
+//!
+//! The blocks, the block infos and the tags share the same tree, their
+//! keys being prefixed with the kind of the entry.
+
+use crate::blockcfg::{Block, HeaderHash};
+use chain_core::property::{Deserialize as _, Serialize as _};
+use chain_storage::{
+    error::Error,
+    store::{BackLink, BlockInfo, BlockStore},
+};
+use std::{io::Read as _, path::Path};
+
+const BLOCK_PREFIX: u8 = b'b';
+const BLOCK_INFO_PREFIX: u8 = b'i';
+const TAG_PREFIX: u8 = b't';
+
+pub struct SledBlockStore {
+    db: sled::Db,
+}
+
+impl SledBlockStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = sled::Db::open(path).map_err(backend_error)?;
+        Ok(SledBlockStore { db })
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<sled::IVec>, Error> {
+        self.db.get(key).map_err(backend_error)
+    }
+
+    fn insert(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), Error> {
+        self.db.insert(key, value).map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+fn backend_error<E>(error: E) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Error::BackendError(Box::new(error))
+}
+
+fn hash_key(prefix: u8, hash: &HeaderHash) -> Result<Vec<u8>, Error> {
+    let mut key = vec![prefix];
+    hash.serialize(&mut key).map_err(backend_error)?;
+    Ok(key)
+}
+
+fn tag_key(tag_name: &str) -> Vec<u8> {
+    let mut key = vec![TAG_PREFIX];
+    key.extend_from_slice(tag_name.as_bytes());
+    key
+}
+
+/// the depth followed by the back links, each one being its distance and
+/// the hash of the ancestor
+fn encode_block_info(block_info: &BlockInfo<HeaderHash>) -> Result<Vec<u8>, Error> {
+    let mut bytes = block_info.depth.to_be_bytes().to_vec();
+    for back_link in &block_info.back_links {
+        bytes.extend_from_slice(&back_link.distance.to_be_bytes());
+        back_link
+            .block_hash
+            .serialize(&mut bytes)
+            .map_err(backend_error)?;
+    }
+    Ok(bytes)
+}
+
+fn decode_block_info(
+    block_hash: &HeaderHash,
+    mut bytes: &[u8],
+) -> Result<BlockInfo<HeaderHash>, std::io::Error> {
+    fn read_u64(bytes: &mut &[u8]) -> Result<u64, std::io::Error> {
+        let mut buf = [0; 8];
+        bytes.read_exact(&mut buf)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    let depth = read_u64(&mut bytes)?;
+    let mut back_links = Vec::new();
+    while !bytes.is_empty() {
+        let distance = read_u64(&mut bytes)?;
+        let block_hash = HeaderHash::deserialize(&mut bytes)?;
+        back_links.push(BackLink {
+            distance,
+            block_hash,
+        });
+    }
+    Ok(BlockInfo {
+        block_hash: block_hash.clone(),
+        depth,
+        back_links,
+    })
+}
+
+impl BlockStore for SledBlockStore {
+    type Block = Block;
+
+    fn put_block_internal(
+        &mut self,
+        block: &Block,
+        block_info: BlockInfo<HeaderHash>,
+    ) -> Result<(), Error> {
+        if self.block_exists(&block_info.block_hash)? {
+            return Err(Error::BlockAlreadyPresent);
+        }
+        // the block info is written last: the block exists only once
+        // both are in the database
+        let block_bytes = block.serialize_as_vec().map_err(backend_error)?;
+        self.insert(hash_key(BLOCK_PREFIX, &block_info.block_hash)?, block_bytes)?;
+        self.insert(
+            hash_key(BLOCK_INFO_PREFIX, &block_info.block_hash)?,
+            encode_block_info(&block_info)?,
+        )
+    }
+
+    fn get_block(&self, block_hash: &HeaderHash) -> Result<(Block, BlockInfo<HeaderHash>), Error> {
+        let block_info = self.get_block_info(block_hash)?;
+        let bytes = self
+            .get(&hash_key(BLOCK_PREFIX, block_hash)?)?
+            .ok_or(Error::BlockNotFound)?;
+        let block = Block::deserialize(bytes.as_ref()).map_err(backend_error)?;
+        Ok((block, block_info))
+    }
+
+    fn get_block_info(&self, block_hash: &HeaderHash) -> Result<BlockInfo<HeaderHash>, Error> {
+        let bytes = self
+            .get(&hash_key(BLOCK_INFO_PREFIX, block_hash)?)?
+            .ok_or(Error::BlockNotFound)?;
+        decode_block_info(block_hash, bytes.as_ref()).map_err(backend_error)
+    }
+
+    fn block_exists(&self, block_hash: &HeaderHash) -> Result<bool, Error> {
+        self.db
+            .contains_key(hash_key(BLOCK_INFO_PREFIX, block_hash)?)
+            .map_err(backend_error)
+    }
+
+    fn put_tag(&mut self, tag_name: &str, block_hash: &HeaderHash) -> Result<(), Error> {
+        self.insert(
+            tag_key(tag_name),
+            block_hash.serialize_as_vec().map_err(backend_error)?,
+        )?;
+        // the tags mark the state of the blockchain to reload on restart,
+        // make sure it is on disk
+        self.db.flush().map_err(backend_error)?;
+        Ok(())
+    }
+
+    fn get_tag(&self, tag_name: &str) -> Result<Option<HeaderHash>, Error> {
+        match self.get(&tag_key(tag_name))? {
+            None => Ok(None),
+            Some(bytes) => HeaderHash::deserialize(bytes.as_ref())
+                .map(Some)
+                .map_err(backend_error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SledBlockStore;
+    use crate::blockchain::testing::ChainBuilder;
+    use chain_storage::{error::Error, memory::MemoryBlockStore, store::BlockStore};
+    use std::{fs, path::PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "jormungandr-sled-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn blocks_and_tags_are_kept_after_reopening() {
+        let dir = temp_dir("reopen");
+        let chain = ChainBuilder::new(100);
+        let mut blocks = vec![chain.block0().clone()];
+        blocks.extend(chain.branch(&chain.block0().header, 1..=20));
+        let tip = blocks.last().unwrap().header.hash();
+
+        // the memory store computes the same block infos
+        let mut memory = MemoryBlockStore::new();
+        {
+            let mut store = SledBlockStore::open(&dir).unwrap();
+            for block in &blocks {
+                store.put_block(block).unwrap();
+                memory.put_block(block).unwrap();
+            }
+            store.put_tag("HEAD", &tip).unwrap();
+        }
+
+        let store = SledBlockStore::open(&dir).unwrap();
+        assert_eq!(store.get_tag("HEAD").unwrap(), Some(tip));
+        assert_eq!(store.get_tag("OTHER").unwrap(), None);
+        for block in &blocks {
+            let hash = block.header.hash();
+            let (stored, info) = store.get_block(&hash).unwrap();
+            let expected = memory.get_block_info(&hash).unwrap();
+            assert_eq!(stored.header.hash(), hash);
+            assert_eq!(info.block_hash, expected.block_hash);
+            assert_eq!(info.depth, expected.depth);
+            assert_eq!(
+                info.back_links
+                    .iter()
+                    .map(|link| (link.distance, link.block_hash.clone()))
+                    .collect::<Vec<_>>(),
+                expected
+                    .back_links
+                    .iter()
+                    .map(|link| (link.distance, link.block_hash.clone()))
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_and_duplicate_blocks_are_reported() {
+        let dir = temp_dir("errors");
+        let chain = ChainBuilder::new(100);
+        let block = chain.branch(&chain.block0().header, 1..=1).remove(0);
+        let mut store = SledBlockStore::open(&dir).unwrap();
+
+        store.put_block(chain.block0()).unwrap();
+        assert!(!store.block_exists(&block.header.hash()).unwrap());
+        match store.get_block(&block.header.hash()) {
+            Err(Error::BlockNotFound) => (),
+            _ => panic!("expected the block not to be found"),
+        }
+
+        store.put_block(&block).unwrap();
+        assert!(store.block_exists(&block.header.hash()).unwrap());
+        match store.put_block(&block) {
+            Err(Error::BlockAlreadyPresent) => (),
+            _ => panic!("expected the block to be already present"),
+        }
+
+        drop(store);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use jormungandr_lib::time::SecondsSinceUnixEpoch;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const SLOT_START_TIME_UNDEFINED: u64 = u64::max_value();
 
@@ -16,6 +16,11 @@ struct StatsCounterImpl {
     block_recv_cnt: AtomicUsize,
     start_time: Instant,
     slot_start_time: AtomicU64,
+    mempool_size: AtomicUsize,
+    block_process_cnt: AtomicU64,
+    block_process_time_us: AtomicU64,
+    leader_event_cnt: AtomicU64,
+    leader_block_cnt: AtomicU64,
 }
 
 impl Default for StatsCounterImpl {
@@ -25,6 +30,11 @@ impl Default for StatsCounterImpl {
             block_recv_cnt: AtomicUsize::default(),
             start_time: Instant::now(),
             slot_start_time: AtomicU64::new(SLOT_START_TIME_UNDEFINED),
+            mempool_size: AtomicUsize::default(),
+            block_process_cnt: AtomicU64::default(),
+            block_process_time_us: AtomicU64::default(),
+            leader_event_cnt: AtomicU64::default(),
+            leader_block_cnt: AtomicU64::default(),
         }
     }
 }
@@ -66,4 +76,53 @@ impl StatsCounter {
         }
         .map(SecondsSinceUnixEpoch::from_secs)
     }
+
+    pub fn set_mempool_size(&self, size: usize) {
+        self.stats.mempool_size.store(size, Ordering::Relaxed)
+    }
+
+    /// number of fragments pending in the pool
+    pub fn mempool_size(&self) -> u64 {
+        self.stats.mempool_size.load(Ordering::Relaxed) as u64
+    }
+
+    pub fn add_block_process_time(&self, time: Duration) {
+        let time_us = time.as_secs() * 1_000_000 + u64::from(time.subsec_micros());
+        self.stats
+            .block_process_time_us
+            .fetch_add(time_us, Ordering::Relaxed);
+        self.stats.block_process_cnt.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// number of blocks processed, whether they were accepted or not
+    pub fn block_process_cnt(&self) -> u64 {
+        self.stats.block_process_cnt.load(Ordering::Relaxed)
+    }
+
+    /// total time spent processing blocks
+    pub fn block_process_time(&self) -> Duration {
+        Duration::from_micros(self.stats.block_process_time_us.load(Ordering::Relaxed))
+    }
+
+    pub fn add_leader_event_cnt(&self, count: usize) {
+        self.stats
+            .leader_event_cnt
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// number of leader events scheduled for this node
+    pub fn leader_event_cnt(&self) -> u64 {
+        self.stats.leader_event_cnt.load(Ordering::Relaxed)
+    }
+
+    pub fn add_leader_block_cnt(&self, count: usize) {
+        self.stats
+            .leader_block_cnt
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// number of blocks created by this node's leaders
+    pub fn leader_block_cnt(&self) -> u64 {
+        self.stats.leader_block_cnt.load(Ordering::Relaxed)
+    }
 }
@@ -0,0 +1,62 @@
+//! In-memory fan-out of events to a changing set of subscribers.
+
+use futures::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// The number of events buffered for each subscriber. A subscriber
+/// lagging further behind is unsubscribed.
+const SUBSCRIBER_BUFFER: usize = 256;
+
+/// Sends copies of events to all the current subscribers.
+///
+/// Sending never blocks: a subscriber which does not keep up with the
+/// events is dropped and its stream ends, so that it does not silently
+/// miss events.
+pub struct Broadcaster<T> {
+    subscribers: Arc<Mutex<Vec<Sender<T>>>>,
+}
+
+impl<T> Broadcaster<T> {
+    pub fn new() -> Self {
+        Broadcaster {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns the stream of the events sent from now on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_BUFFER);
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+}
+
+impl<T: Clone> Broadcaster<T> {
+    pub fn send(&self, event: T) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+        let previous = std::mem::replace(&mut *subscribers, Vec::new());
+        *subscribers = previous
+            .into_iter()
+            .filter_map(|mut subscriber| {
+                subscriber.try_send(event.clone()).ok().map(|()| subscriber)
+            })
+            .collect();
+    }
+}
+
+impl<T> Clone for Broadcaster<T> {
+    fn clone(&self) -> Self {
+        Broadcaster {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<T> Default for Broadcaster<T> {
+    fn default() -> Self {
+        Broadcaster::new()
+    }
+}